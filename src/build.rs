@@ -1,9 +1,22 @@
 // src/build.rs
 
 use crate::context::Context;
+use crate::diagnostics::{Diagnostics, DiagnosticsFormat, BUILD_LOG_FILE};
+use crate::config::load_config;
+use crate::initialize_git::JUCE_SUBMODULE_PATH;
+use crate::manifest::JuceSource;
+use crate::manifest::ProjectManifest;
+use crate::presets::{cache_variables, Preset, PRESETS_FILE, USER_PRESETS_FILE};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
 // use std::path::PathBuf;
 use thiserror::Error; // For structured errors
 use which::which;
@@ -15,6 +28,8 @@ pub enum BuildError {
     Io(#[from] std::io::Error),
     #[error("UTF-8 conversion error: {0}")]
     Utf8(#[from] std::str::Utf8Error),
+    #[error("cmake not found on the PATH; run `jumake doctor` to check your build tools")]
+    CMakeNotFound,
     #[error("CMake configuration failed")]
     CMakeConfigureFailed,
     #[error("CMake build failed")]
@@ -23,77 +38,229 @@ pub enum BuildError {
     ExecutableNotFound(String),
     #[error("compile_commands.json not found")]
     CompileCommandsMissing,
+    #[error("Running the executable failed: {0}")]
+    RunFailed(String),
 }
 
-/// Build the project using CMake, optionally leveraging ccache.
-pub fn build_project(context: &Context) -> Result<(), BuildError> {
-    println!("Building project '{}' in '{}'...", context.project_name, context.build_type);
+/// Directory below the project holding all builds.
+pub const BUILD_DIR: &str = "jumake_build";
 
-    let build_dir = context.project_path.join("jumake_build");
-    fs::create_dir_all(&build_dir)?; // Ensure build directory exists
+/// Cache variables of every configure, with or without presets.
+pub const JUMAKE_CACHE_VARIABLES: &[(&str, &str)] = &[("CMAKE_EXPORT_COMPILE_COMMANDS", "ON")];
 
-    // Prefer Ninja if installed, fallback to Unix Makefiles
-    let generator = if Command::new("ninja").output().is_ok() {
-        "Ninja"
-    } else {
-        "Unix Makefiles"
-    };
+/// File in each build directory recording the inputs of its last configure.
+pub const CONFIGURE_INPUTS_FILE: &str = "jumake-configure-inputs.txt";
 
-    let cmake_cache = build_dir.join("CMakeCache.txt");
+/// Configure inputs that require discarding the cache when they change.
+const FRESH_INPUTS: &[&str] = &["JUCE", "CC", "CXX"];
+
+/// Cache variables that CMake only reads on the first configure.
+const FRESH_OPTIONS: &[&str] = &["CMAKE_TOOLCHAIN_FILE", "CMAKE_C_COMPILER", "CMAKE_CXX_COMPILER"];
+
+/// Cache variables naming a compiler, which CMake looks up on the PATH.
+const COMPILER_OPTIONS: &[&str] = &["CMAKE_C_COMPILER", "CMAKE_CXX_COMPILER"];
+
+/// When `jumake build` runs the CMake configure step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigureMode {
+    /// Only if the build directory is not configured or out of date
+    #[default]
+    Auto,
+    /// Always, on top of the existing cache
+    Always,
+    /// Always, discarding the existing cache (`cmake --fresh`)
+    Fresh,
+}
 
-    // Only configure CMake if cache doesn't exist
-    if !cmake_cache.exists() {
-        println!("Running CMake configuration...");
+/// Options of `jumake configure`, `jumake build` and `jumake run`.
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// How compiler diagnostics are reported after the build.
+    pub diagnostics: DiagnosticsFormat,
+    /// Configure and build with this CMake preset instead of JuMake's own settings.
+    pub preset: Option<Preset>,
+    /// When to run the configure step.
+    pub configure: ConfigureMode,
+}
+
+impl BuildOptions {
+    /// Whether stdout is reserved for machine-readable diagnostics, moving everything else to stderr.
+    fn machine_readable(&self) -> bool {
+        self.diagnostics != DiagnosticsFormat::Text
+    }
+}
 
-        let ccache_enabled = which("ccache").is_ok();
-        if ccache_enabled {
-            println!("⚡ Detected ccache — enabling compiler caching!");
+/// Prints a progress message, on stderr if stdout is reserved for machine-readable diagnostics.
+macro_rules! progress {
+    ($options:expr, $($arg:tt)*) => {
+        if $options.machine_readable() {
+            eprintln!($($arg)*)
         } else {
-            println!("⚠️  ccache not found — building without compiler cache.");
+            println!($($arg)*)
         }
+    };
+}
 
-        let mut cmake_cmd = Command::new("cmake");
-        cmake_cmd
-            .arg("..")
-            .arg(format!("-G{}", generator))
-            .arg(format!("-DCMAKE_BUILD_TYPE={}", context.build_type))
-            .arg("-DCMAKE_EXPORT_COMPILE_COMMANDS=ON");
+/// Maps a failure to start `cmake` to [`BuildError::CMakeNotFound`] if it isn't installed.
+pub(crate) fn cmake_spawn_error(error: std::io::Error) -> BuildError {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => BuildError::CMakeNotFound,
+        _ => BuildError::Io(error),
+    }
+}
 
-        if ccache_enabled {
-            cmake_cmd
-                .arg("-DCMAKE_C_COMPILER_LAUNCHER=ccache")
-                .arg("-DCMAKE_CXX_COMPILER_LAUNCHER=ccache");
-        }
+/// Configure the project with CMake unless its build directory is up to date, optionally
+/// leveraging ccache. Returns the build directory.
+///
+/// A build directory is configured again if its cache doesn't match the project (see
+/// [`stale_cache`]) or the inputs of its last configure changed (see [`changed_inputs`]), and
+/// always with [`ConfigureMode::Always`] or [`ConfigureMode::Fresh`].
+///
+/// With a preset in `options`, `cmake --preset` is used instead of JuMake's own configure
+/// arguments, configuring the preset's binary directory.
+pub fn configure_project(context: &Context, options: &BuildOptions) -> Result<PathBuf, BuildError> {
+    let generator = project_generator(&context.manifest);
+    let configure_args = configure_args(context);
+    let (build_dir, wanted) = match &options.preset {
+        Some(preset) => (
+            preset.binary_dir.clone(),
+            WantedCache {
+                generator: preset.generator.as_deref(),
+                build_type: preset.build_type.as_deref(),
+                args: &[],
+            },
+        ),
+        None => (
+            build_directory(&context.project_path, &generator, &context.build_type),
+            WantedCache {
+                generator: Some(&generator),
+                build_type: (!is_multi_config(&generator)).then_some(context.build_type.as_str()),
+                args: &configure_args,
+            },
+        ),
+    };
+    fs::create_dir_all(&build_dir)?; // Ensure build directory exists
 
-        let status = cmake_cmd
-            .current_dir(&build_dir)
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()?;
+    let legacy_cache = context.project_path.join(BUILD_DIR).join("CMakeCache.txt");
+    if build_dir.parent() == Some(&context.project_path.join(BUILD_DIR)) && legacy_cache.exists() {
+        progress!(
+            options,
+            "⚠️  {}/ holds a build of an older JuMake version or another generator; {} builds go to {}/<build type>/.",
+            BUILD_DIR,
+            generator,
+            BUILD_DIR
+        );
+    }
 
-        if !status.success() {
-            return Err(BuildError::CMakeConfigureFailed);
+    let ccache_enabled = which("ccache").is_ok();
+    let fetchcontent_source_dir = fetchcontent_source_dir(context);
+
+    let mut cmake_cmd = configure_command(context, options, &generator, &build_dir);
+
+    // Only configure CMake if there is no cache yet, it doesn't match the wanted settings or
+    // the inputs changed since the last configure
+    let inputs = configure_inputs(context, options, &cmake_cmd);
+    let cmake_cache = build_dir.join("CMakeCache.txt");
+    let stale = read_cmake_cache(&cmake_cache).map(|cache| {
+        stale_cache(&cache, &context.project_path, &build_dir, &wanted)
+            .or_else(|| changed_inputs(&build_dir, &inputs))
+    });
+    let fresh = match (&stale, options.configure) {
+        (None, _) => {
+            progress!(options, "Running CMake configuration...");
+            false
+        }
+        (Some(_), ConfigureMode::Fresh) => {
+            progress!(options, "Configuring from scratch...");
+            true
+        }
+        (Some(Some(Stale::Fresh(reason))), _) => {
+            progress!(options, "⚠️  {} — configuring from scratch...", reason);
+            true
+        }
+        (Some(Some(Stale::Reconfigure(reason))), _) => {
+            progress!(options, "{} — configuring again...", reason);
+            false
+        }
+        (Some(None), ConfigureMode::Always) => {
+            progress!(options, "Configuring again...");
+            false
+        }
+        (Some(None), ConfigureMode::Auto) => {
+            progress!(options, "CMake already configured, skipping configure step...");
+            return Ok(build_dir);
         }
+    };
+
+    if ccache_enabled {
+        progress!(options, "⚡ Detected ccache — enabling compiler caching!");
     } else {
-        println!("CMake already configured, skipping configure step...");
+        progress!(options, "⚠️  ccache not found — building without compiler cache.");
+    }
+    if let Some(source_dir) = &fetchcontent_source_dir {
+        progress!(options, "Using local JUCE checkout from FETCHCONTENT_SOURCE_DIR_JUCE: {}", source_dir);
+    }
+    // A generator can only be chosen for a new build directory
+    if options.preset.as_ref().is_some_and(|preset| preset.generator.is_none()) && (fresh || stale.is_none()) {
+        cmake_cmd.arg(format!("-G{}", generator));
+    }
+    if fresh {
+        // Removes CMakeCache.txt and CMakeFiles/ first
+        cmake_cmd.arg("--fresh");
     }
 
-    // Build the project
-    
-    let num_cpus = std::cmp::max(num_cpus::get() - 2, 2);
-    let status = Command::new("cmake")
-        .arg("--build")
-        .arg(".")
-        .arg("--config")
-        .arg(&context.build_type)
-        .arg("--parallel")
-        .arg(num_cpus.to_string())
-        .current_dir(&build_dir)
-        .stdout(Stdio::inherit())
+    let status = cmake_cmd
+        .stdout(if options.machine_readable() { Stdio::from(io::stderr()) } else { Stdio::inherit() })
         .stderr(Stdio::inherit())
-        .status()?;
+        .status()
+        .map_err(cmake_spawn_error)?;
 
     if !status.success() {
+        if !fresh && stale.is_some() {
+            progress!(options, "If the build directory is out of date, try `jumake configure --fresh`.");
+        }
+        return Err(BuildError::CMakeConfigureFailed);
+    }
+    write_configure_inputs(&build_dir, &inputs)?;
+    Ok(build_dir)
+}
+
+/// Build the project using CMake, configuring it first if needed.
+///
+/// With a preset in `options`, `cmake --build --preset` is used if there is a build preset of
+/// that name.
+///
+/// The output of `cmake --build` is shown as it comes and saved to `build.log` in the build
+/// directory; the compiler diagnostics found in it are then reported as selected in `options`.
+pub fn build_project(context: &Context, options: &BuildOptions) -> Result<(), BuildError> {
+    progress!(options, "Building project '{}' in '{}'...", context.project_name, context.build_type);
+
+    let build_dir = configure_project(context, options)?;
+
+    // Build the project
+    let mut build_cmd = build_command(context, options, &build_dir);
+    let (success, output) = run_captured(&mut build_cmd, options.machine_readable())?;
+
+    // Keep the raw output for reference and report the diagnostics found in it
+    let log_path = build_dir.join(BUILD_LOG_FILE);
+    fs::write(&log_path, &output)?;
+    let diagnostics = Diagnostics::parse(&output, &build_dir);
+    match options.diagnostics {
+        DiagnosticsFormat::Text => {
+            diagnostics.print_summary(&context.project_path);
+            if !success {
+                println!("Full build log: {}", log_path.display());
+            }
+        }
+        DiagnosticsFormat::Json => println!("{}", diagnostics.to_json(&log_path)),
+        DiagnosticsFormat::Gcc => {
+            for line in diagnostics.to_gcc() {
+                println!("{}", line);
+            }
+        }
+    }
+
+    if !success {
         return Err(BuildError::CMakeBuildFailed);
     }
 
@@ -101,35 +268,347 @@ pub fn build_project(context: &Context) -> Result<(), BuildError> {
     if !cfg!(target_os = "windows") {
         let compile_commands_path = build_dir.join("compile_commands.json");
         if compile_commands_path.exists() {
-            fs::copy(&compile_commands_path, &context.project_path.join("compile_commands.json"))?;
-            println!("Moved compile_commands.json to the project root.");
+            fs::copy(&compile_commands_path, context.project_path.join("compile_commands.json"))?;
+            progress!(options, "Moved compile_commands.json to the project root.");
+        } else if options.preset.is_some() || is_multi_config(&cached_generator(&build_dir)) {
+            // User presets may not export compile commands, and Xcode can't
+            progress!(
+                options,
+                "⚠️  No compile_commands.json was generated; the one in the project root is not updated."
+            );
         } else {
             return Err(BuildError::CompileCommandsMissing);
         }
     }
 
-    println!("Build successful!");
+    progress!(options, "Build successful!");
+    Ok(())
+}
+
+/// The `cmake` configure command for `build_dir`, apart from the flags that depend on the state
+/// of the directory (`--fresh`, and a generator for presets without one).
+pub fn configure_command(context: &Context, options: &BuildOptions, generator: &str, build_dir: &Path) -> Command {
+    let mut cmake_cmd = Command::new("cmake");
+    match &options.preset {
+        Some(preset) => {
+            cmake_cmd.arg("--preset").arg(&preset.configure_preset).current_dir(&context.project_path);
+        }
+        None => {
+            cmake_cmd
+                .arg("-S")
+                .arg(&context.project_path)
+                .arg("-B")
+                .arg(".")
+                .arg(format!("-G{}", generator))
+                .current_dir(build_dir);
+            if !is_multi_config(generator) {
+                cmake_cmd.arg(format!("-DCMAKE_BUILD_TYPE={}", context.build_type));
+            }
+            cmake_cmd
+                .args(JUMAKE_CACHE_VARIABLES.iter().map(|(key, value)| format!("-D{}={}", key, value)))
+                .args(configure_args(context));
+        }
+    }
+
+    // Let offline FetchContent builds use a local JUCE checkout
+    if let Some(source_dir) = fetchcontent_source_dir(context) {
+        cmake_cmd.arg(format!("-DFETCHCONTENT_SOURCE_DIR_JUCE={}", source_dir));
+    }
+
+    if which("ccache").is_ok() {
+        cmake_cmd
+            .arg("-DCMAKE_C_COMPILER_LAUNCHER=ccache")
+            .arg("-DCMAKE_CXX_COMPILER_LAUNCHER=ccache");
+    }
+    cmake_cmd
+}
+
+/// The `cmake --build` command for `build_dir`, with the target, jobs and extra arguments from
+/// jumake.toml.
+pub fn build_command(context: &Context, options: &BuildOptions, build_dir: &Path) -> Command {
+    let settings = &context.manifest.build;
+    let jobs = settings.jobs.unwrap_or_else(|| std::cmp::max(num_cpus::get().saturating_sub(2), 2));
+    let mut build_cmd = Command::new("cmake");
+    match options.preset.as_ref().and_then(|preset| preset.build_preset.as_ref()) {
+        Some(build_preset) => {
+            build_cmd.arg("--build").arg("--preset").arg(build_preset).current_dir(&context.project_path)
+        }
+        None => build_cmd.arg("--build").arg(".").arg("--config").arg(&context.build_type).current_dir(build_dir),
+    };
+    build_cmd.arg("--parallel").arg(jobs.to_string());
+    if let Some(target) = &settings.target {
+        build_cmd.arg("--target").arg(target);
+    }
+    build_cmd.args(&settings.build_args);
+    build_cmd
+}
+
+/// The local JUCE checkout for FetchContent projects from `FETCHCONTENT_SOURCE_DIR_JUCE`.
+fn fetchcontent_source_dir(context: &Context) -> Option<String> {
+    std::env::var("FETCHCONTENT_SOURCE_DIR_JUCE")
+        .ok()
+        .filter(|_| matches!(context.manifest.juce, JuceSource::FetchContent { .. }))
+}
+
+/// Whether `generator` builds all configurations from a single build directory.
+pub fn is_multi_config(generator: &str) -> bool {
+    generator == "Ninja Multi-Config" || generator == "Xcode" || generator.starts_with("Visual Studio")
+}
+
+/// The generator from jumake.toml or the global configuration, otherwise Ninja if installed,
+/// fallback to Unix Makefiles.
+pub fn project_generator(manifest: &ProjectManifest) -> String {
+    let default_generator = load_config().ok().and_then(|config| config.default_generator);
+    match manifest.build.generator.clone().or(default_generator) {
+        Some(generator) => generator,
+        None if which("ninja").is_ok() => String::from("Ninja"),
+        None => String::from("Unix Makefiles"),
+    }
+}
+
+/// Build directory for `build_type`: `jumake_build/<build type>` with single-configuration
+/// generators, `jumake_build` itself with multi-configuration ones.
+pub fn build_directory(project_path: &Path, generator: &str, build_type: &str) -> PathBuf {
+    let build_root = project_path.join(BUILD_DIR);
+    if is_multi_config(generator) {
+        build_root
+    } else {
+        build_root.join(build_type)
+    }
+}
+
+/// The configured build directories of the project: `jumake_build` and its subdirectories
+/// holding a `CMakeCache.txt`.
+pub fn configured_build_dirs(project_path: &Path) -> Vec<PathBuf> {
+    let build_root = project_path.join(BUILD_DIR);
+    let mut dirs: Vec<PathBuf> = fs::read_dir(&build_root)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    dirs.sort();
+    dirs.insert(0, build_root);
+    dirs.retain(|dir| dir.join("CMakeCache.txt").is_file());
+    dirs
+}
+
+/// The settings a build directory should have been configured with; `None` accepts any value.
+struct WantedCache<'a> {
+    generator: Option<&'a str>,
+    build_type: Option<&'a str>,
+    /// CMake arguments whose `-D` options must match the cache
+    args: &'a [String],
+}
+
+/// Why an existing CMake cache can't be used as it is.
+enum Stale {
+    /// The cache has to be discarded (`cmake --fresh`)
+    Fresh(String),
+    /// Configuring again on top of the cache updates it
+    Reconfigure(String),
+}
+
+/// Compares the cache of `build_dir` with the wanted settings.
+fn stale_cache(
+    cache: &HashMap<String, String>,
+    project_path: &Path,
+    build_dir: &Path,
+    wanted: &WantedCache,
+) -> Option<Stale> {
+    // A copied or moved project keeps a cache pointing at the old location
+    if let Some(home) = cache.get("CMAKE_HOME_DIRECTORY") {
+        if !same_path(Path::new(home), project_path) {
+            return Some(Stale::Fresh(format!("The build directory was configured for {}", home)));
+        }
+    }
+    let cached = |key: &str| cache.get(key).map(String::as_str).unwrap_or_default();
+    if let Some(generator) = wanted.generator {
+        if cached("CMAKE_GENERATOR") != generator {
+            let reason =
+                format!("The build directory was configured with {}, not {}", cached("CMAKE_GENERATOR"), generator);
+            return Some(Stale::Fresh(reason));
+        }
+    }
+    if let Some(build_type) = wanted.build_type {
+        if cached("CMAKE_BUILD_TYPE") != build_type {
+            return Some(Stale::Reconfigure(format!("The build type changed to {}", build_type)));
+        }
+    }
+    let (variables, _) = cache_variables(wanted.args);
+    for (key, value) in variables {
+        // CMake stores PATH and FILEPATH values as absolute paths, and compilers found on the PATH
+        // with their full path
+        let is_compiler = COMPILER_OPTIONS.contains(&key.as_str());
+        let matches = cache.get(&key).is_some_and(|cached| {
+            *cached == value
+                || same_path(Path::new(cached), &build_dir.join(&value))
+                || is_compiler && which(&value).is_ok_and(|found| same_path(Path::new(cached), &found))
+        });
+        if !matches {
+            let reason = format!("The option {} changed", key);
+            let fresh = FRESH_OPTIONS.contains(&key.as_str());
+            return Some(if fresh { Stale::Fresh(reason) } else { Stale::Reconfigure(reason) });
+        }
+    }
+    None
+}
+
+/// JuMake's own configure arguments from jumake.toml: the toolchain file and `cmake_args`.
+fn configure_args(context: &Context) -> Vec<String> {
+    let settings = &context.manifest.build;
+    let toolchain = settings.toolchain.as_ref().map(|toolchain| {
+        format!("-DCMAKE_TOOLCHAIN_FILE={}", context.project_path.join(toolchain).display())
+    });
+    toolchain.into_iter().chain(settings.cmake_args.iter().cloned()).collect()
+}
+
+/// Whether both paths exist and point at the same file or directory.
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Reads the `KEY:TYPE=VALUE` entries of a `CMakeCache.txt`.
+pub fn read_cmake_cache(cache_path: &Path) -> Option<HashMap<String, String>> {
+    let content = fs::read_to_string(cache_path).ok()?;
+    Some(
+        content
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with("//"))
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                let key = key.split_once(':').map_or(key, |(key, _)| key);
+                Some((key.to_string(), value.to_string()))
+            })
+            .collect(),
+    )
+}
+
+/// The generator a build directory was configured with.
+fn cached_generator(build_dir: &Path) -> String {
+    read_cmake_cache(&build_dir.join("CMakeCache.txt"))
+        .and_then(|mut cache| cache.remove("CMAKE_GENERATOR"))
+        .unwrap_or_default()
+}
+
+/// What a configure depends on besides the CMake files, which CMake tracks itself: the
+/// command line, where JUCE comes from, the compilers from the environment and, when configuring
+/// from a preset, the preset files.
+fn configure_inputs(context: &Context, options: &BuildOptions, cmake_cmd: &Command) -> Vec<(&'static str, String)> {
+    let command: Vec<String> = cmake_cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    let juce = match &context.manifest.juce {
+        JuceSource::Symlink { .. } => {
+            let linked = context.project_path.join(JUCE_SUBMODULE_PATH);
+            let target = linked.canonicalize().unwrap_or(linked);
+            format!("symlink to {}", target.display())
+        }
+        JuceSource::Submodule { url, rev } => format!("submodule {}@{}", url, rev.as_deref().unwrap_or("HEAD")),
+        JuceSource::FetchContent { url, rev, hash } => format!(
+            "fetchcontent {} {}",
+            url,
+            rev.as_deref().or(hash.as_deref()).unwrap_or_default()
+        ),
+    };
+    let mut inputs = vec![
+        ("command", format!("cmake {}", command.join(" "))),
+        ("JUCE", juce),
+        ("CC", std::env::var("CC").unwrap_or_default()),
+        ("CXX", std::env::var("CXX").unwrap_or_default()),
+    ];
+    if options.preset.is_some() {
+        let presets: String = [PRESETS_FILE, USER_PRESETS_FILE]
+            .iter()
+            .map(|file| fs::read_to_string(context.project_path.join(file)).unwrap_or_default())
+            .collect();
+        let mut hasher = DefaultHasher::new();
+        presets.hash(&mut hasher);
+        inputs.push(("presets", format!("{:016x}", hasher.finish())));
+    }
+    inputs
+}
+
+/// Compares `inputs` with the ones recorded by the last configure of `build_dir`. Build
+/// directories configured without JuMake's record are taken as up to date.
+fn changed_inputs(build_dir: &Path, inputs: &[(&str, String)]) -> Option<Stale> {
+    let recorded = fs::read_to_string(build_dir.join(CONFIGURE_INPUTS_FILE)).ok()?;
+    let recorded: HashMap<&str, &str> = recorded
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(" = "))
+        .collect();
+    let (name, _) = inputs.iter().find(|(name, value)| recorded.get(name) != Some(&value.as_str()))?;
+    let reason = format!("The configure input '{}' changed", name);
+    Some(if FRESH_INPUTS.contains(name) { Stale::Fresh(reason) } else { Stale::Reconfigure(reason) })
+}
+
+fn write_configure_inputs(build_dir: &Path, inputs: &[(&str, String)]) -> Result<(), BuildError> {
+    let mut content = String::from("# Inputs of the last CMake configure, compared before each build\n");
+    for (name, value) in inputs {
+        content.push_str(&format!("{} = {}\n", name, value));
+    }
+    fs::write(build_dir.join(CONFIGURE_INPUTS_FILE), content)?;
     Ok(())
 }
 
+/// Runs `command`, echoing its output while capturing it; stdout is echoed to stderr if
+/// `to_stderr` is set. Returns whether the command succeeded and its combined output.
+fn run_captured(command: &mut Command, to_stderr: bool) -> Result<(bool, String), BuildError> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(cmake_spawn_error)?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    // Lines of both streams are collected in the order they arrive
+    let output = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        scope.spawn(|| tee(stdout, &output, to_stderr));
+        scope.spawn(|| tee(stderr, &output, true));
+    });
+    let status = child.wait()?;
+    let output = output.into_inner().unwrap_or_else(PoisonError::into_inner);
+    Ok((status.success(), String::from_utf8_lossy(&output).into_owned()))
+}
+
+/// Copies `stream` line by line to stdout or stderr and to `output`.
+fn tee(stream: impl Read, output: &Mutex<Vec<u8>>, to_stderr: bool) {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut line), Ok(read) if read > 0) {
+        // Echoing is best effort: a closed terminal must not abort the build
+        let _ = if to_stderr { io::stderr().write_all(&line) } else { io::stdout().write_all(&line) };
+        output.lock().unwrap_or_else(PoisonError::into_inner).extend_from_slice(&line);
+        line.clear();
+    }
+}
+
 /// Run the built project executable
-pub fn run_project(context: &Context) -> Result<(), BuildError> {
+pub fn run_project(context: &Context, options: &BuildOptions) -> Result<(), BuildError> {
     // Ensure project is built first
-    build_project(context)?;
+    build_project(context, options)?;
 
     println!("Running project '{}'...", context.project_name);
 
-    let executable_path = find_executable(context)?;
+    let generator = project_generator(&context.manifest);
+    let build_dir = build_directory(&context.project_path, &generator, &context.build_type);
+    let executable_path = find_executable(context, &build_dir)?;
 
     // MacOS special handling for non-console apps
-    if cfg!(target_os = "macos") && context.template_name.as_deref() != Some("ConsoleApp") {
+    let status = if cfg!(target_os = "macos") && context.template_name.as_deref() != Some("ConsoleApp") {
         Command::new("open")
-            .arg(executable_path)
-            .status()?;
+            .arg(&executable_path)
+            .status()
     } else {
-        Command::new(executable_path)
-            .current_dir(context.project_path.join("jumake_build"))
-            .status()?;
+        Command::new(&executable_path)
+            .current_dir(&build_dir)
+            .status()
+    }
+    .map_err(|e| BuildError::RunFailed(format!("cannot start {}: {}", executable_path, e)))?;
+
+    if !status.success() {
+        return Err(BuildError::RunFailed(format!("{} exited with {}", executable_path, status)));
     }
 
     println!("Execution completed.");
@@ -137,8 +616,7 @@ pub fn run_project(context: &Context) -> Result<(), BuildError> {
 }
 
 /// Find the project executable in the build directory
-fn find_executable(context: &Context) -> Result<String, BuildError> {
-    let build_dir = context.project_path.join("jumake_build");
+fn find_executable(context: &Context, build_dir: &Path) -> Result<String, BuildError> {
 
     // Prepare OS-specific find commands
     let output = if cfg!(target_os = "windows") {
@@ -149,7 +627,7 @@ fn find_executable(context: &Context) -> Result<String, BuildError> {
         Command::new("powershell")
             .arg("-Command")
             .arg(&cmd)
-            .current_dir(&build_dir)
+            .current_dir(build_dir)
             .output()?
     } else {
         let cmd = match (cfg!(target_os = "macos"), context.template_name.as_deref()) {
@@ -0,0 +1,136 @@
+// src/clean.rs
+//! `jumake clean`: removing build outputs, CMake caches or whole build directories.
+//!
+//! Like `remove` and `rename`, the work is first collected into a plan, which is either printed
+//! (`--dry-run`) or carried out. Every path in the plan must lie inside the project root;
+//! otherwise nothing is deleted at all.
+
+use crate::build::{cmake_spawn_error, configured_build_dirs, BuildError, BUILD_DIR, CONFIGURE_INPUTS_FILE};
+use crate::manifest::LEGACY_BUILD_TYPE_FILE;
+use anyhow::{bail, Context as AnyhowContext, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What `jumake clean` removes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CleanMode {
+    /// The build outputs of each build directory (`cmake --build <dir> --target clean`)
+    #[default]
+    Outputs,
+    /// The CMake cache of each build directory, so the next build configures from scratch
+    Cache,
+    /// The build directories, the copied `compile_commands.json` and the legacy `.jumake` file
+    All,
+}
+
+/// Options of `jumake clean`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanOptions {
+    pub mode: CleanMode,
+    /// Only print what would be deleted.
+    pub dry_run: bool,
+}
+
+/// A single step of the plan.
+#[derive(Debug)]
+enum Step {
+    /// Run the `clean` target of a build directory
+    CleanTarget(PathBuf),
+    /// Delete a file, directory or symbolic link
+    Delete(PathBuf),
+}
+
+impl Step {
+    fn path(&self) -> &Path {
+        match self {
+            Step::CleanTarget(path) | Step::Delete(path) => path,
+        }
+    }
+}
+
+/// Cleans the project at `project_path` as selected by `options`.
+pub fn clean_project(project_path: &Path, options: CleanOptions) -> Result<()> {
+    let steps = plan(project_path, options.mode);
+    let root = project_path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve the project root {}", project_path.display()))?;
+    for step in &steps {
+        check_inside(&root, step.path())?;
+    }
+
+    let display = |path: &Path| path.strip_prefix(project_path).unwrap_or(path).display().to_string();
+    if steps.is_empty() {
+        println!("Nothing to clean.");
+        return Ok(());
+    }
+    if options.dry_run {
+        println!("Dry run — nothing will be deleted:");
+        for step in &steps {
+            match step {
+                Step::CleanTarget(dir) => println!("  clean   {} (cmake --target clean)", display(dir)),
+                Step::Delete(path) => println!("  delete  {}", display(path)),
+            }
+        }
+        return Ok(());
+    }
+
+    for step in &steps {
+        match step {
+            Step::CleanTarget(dir) => {
+                let status = Command::new("cmake")
+                    .arg("--build")
+                    .arg(dir)
+                    .args(["--target", "clean"])
+                    .status()
+                    .map_err(cmake_spawn_error)?;
+                if !status.success() {
+                    return Err(BuildError::CMakeBuildFailed)
+                        .with_context(|| format!("Failed to clean {}", display(dir)));
+                }
+                println!("Cleaned {}", display(dir));
+            }
+            Step::Delete(path) => {
+                let metadata = fs::symlink_metadata(path)?;
+                // A symbolic link is removed itself; its target is left alone
+                let result =
+                    if metadata.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+                result.with_context(|| format!("Failed to delete {}", path.display()))?;
+                println!("Deleted {}", display(path));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The steps for `mode`, leaving out files that don't exist.
+fn plan(project_path: &Path, mode: CleanMode) -> Vec<Step> {
+    let exists = |path: &PathBuf| fs::symlink_metadata(path).is_ok();
+    match mode {
+        CleanMode::Outputs => configured_build_dirs(project_path).into_iter().map(Step::CleanTarget).collect(),
+        CleanMode::Cache => configured_build_dirs(project_path)
+            .iter()
+            .flat_map(|dir| ["CMakeCache.txt", "CMakeFiles", CONFIGURE_INPUTS_FILE].map(|name| dir.join(name)))
+            .filter(exists)
+            .map(Step::Delete)
+            .collect(),
+        CleanMode::All => [BUILD_DIR, "compile_commands.json", LEGACY_BUILD_TYPE_FILE]
+            .iter()
+            .map(|name| project_path.join(name))
+            .filter(exists)
+            .map(Step::Delete)
+            .collect(),
+    }
+}
+
+/// Fails unless `path` lies below the canonical project `root`.
+///
+/// Only the parent directory is resolved: a symbolic link to a build directory elsewhere may be
+/// deleted, but nothing reached through it.
+fn check_inside(root: &Path, path: &Path) -> Result<()> {
+    let parent = path.parent().and_then(|parent| parent.canonicalize().ok());
+    if path.file_name().is_none() || !parent.is_some_and(|parent| parent.starts_with(root)) {
+        bail!("Refusing to delete {}: it is outside the project root {}", path.display(), root.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,508 @@
+// src/cmake_edit.rs
+//! Structure-aware editing of CMake files.
+//!
+//! [`CMakeDocument`] tokenizes a CMake file into commands and their arguments while keeping
+//! the original text. Edits splice the text directly, so comments, indentation and the
+//! trailing newline are preserved and only the touched arguments change.
+
+use anyhow::Result;
+use std::cmp::Ordering;
+
+/// Keywords that start an argument section in `target_*` commands.
+pub const SCOPE_KEYWORDS: &[&str] = &["PRIVATE", "PUBLIC", "INTERFACE"];
+
+/// Indentation added below a keyword for its arguments when none can be copied.
+const INDENT_STEP: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ElementKind {
+    Argument,
+    Comment,
+}
+
+/// An argument or comment inside a command invocation.
+#[derive(Debug, Clone)]
+struct Element {
+    kind: ElementKind,
+    start: usize,
+    end: usize,
+}
+
+/// A command invocation like `target_sources(...)`.
+#[derive(Debug, Clone)]
+struct Command {
+    name: String,
+    start: usize,
+    close_paren: usize,
+    elements: Vec<Element>,
+}
+
+/// A parsed CMake file that can be edited in place.
+#[derive(Debug, Clone)]
+pub struct CMakeDocument {
+    text: String,
+    commands: Vec<Command>,
+}
+
+/// Identifies a command within a [`CMakeDocument`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandId(usize);
+
+impl CMakeDocument {
+    /// Parses `text`, failing on unbalanced parentheses or unterminated strings.
+    pub fn parse(text: &str) -> Result<Self> {
+        let commands = Parser { text, pos: 0 }.parse_file()?;
+        Ok(Self { text: text.to_string(), commands })
+    }
+
+    /// Consumes the document and returns its contents.
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Finds the first command called `name` (case-insensitive) whose first argument is
+    /// `first_arg`, if given.
+    pub fn find_command(&self, name: &str, first_arg: Option<&str>) -> Option<CommandId> {
+        self.commands
+            .iter()
+            .position(|command| {
+                command.name.eq_ignore_ascii_case(name)
+                    && first_arg.is_none_or(|expected| {
+                        self.arguments(command).next().map(|e| self.value(e)) == Some(expected)
+                    })
+            })
+            .map(CommandId)
+    }
+
+    /// Finds all commands called `name` (case-insensitive), in file order.
+    pub fn find_commands(&self, name: &str) -> Vec<CommandId> {
+        (0..self.commands.len())
+            .filter(|&index| self.commands[index].name.eq_ignore_ascii_case(name))
+            .map(CommandId)
+            .collect()
+    }
+
+    /// Returns all argument values of the command `id`, without quotes.
+    pub fn command_arguments(&self, id: CommandId) -> Vec<String> {
+        let command = self.command(id);
+        self.arguments(command).map(|e| unquote(self.value(e)).to_string()).collect()
+    }
+
+    /// Returns the argument values of the section started by `keyword`, without quotes.
+    pub fn section(&self, id: CommandId, keyword: &str) -> Vec<String> {
+        self.section_elements(id, keyword)
+            .map(|elements| elements.iter().map(|e| unquote(self.value(e)).to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the section started by `keyword` contains an argument equivalent to
+    /// `value` (see [`same_argument`]).
+    pub fn contains(&self, id: CommandId, keyword: &str, value: &str) -> bool {
+        self.section(id, keyword).iter().any(|arg| same_argument(arg, value))
+    }
+
+    /// Inserts `value` into the section started by `keyword`, creating the section if needed.
+    ///
+    /// If the existing arguments are sorted, the section is kept sorted; otherwise `value` is
+    /// appended. Arguments listed one per line stay one per line.
+    pub fn insert_argument(&mut self, id: CommandId, keyword: &str, value: &str) -> Result<()> {
+        let command = self.command(id).clone();
+
+        let mut keep_sorted = false;
+        let (position, insertion) = match self.section_elements(id, keyword) {
+            Some(section) if !section.is_empty() => {
+                let values: Vec<&str> = section.iter().map(|e| self.value(e)).collect();
+                keep_sorted = values.windows(2).all(|w| compare_arguments(w[0], w[1]) != Ordering::Greater);
+                let last = section.last().expect("section is not empty");
+                if self.starts_line(last.start) {
+                    (self.line_comment_end(last.end), format!("\n{}{}", self.indent_of(last.start), value))
+                } else {
+                    (last.end, format!(" {}", value))
+                }
+            }
+            Some(_) => {
+                let keyword_element = self
+                    .keyword_element(&command, keyword)
+                    .expect("section exists, so the keyword does");
+                if self.starts_line(keyword_element.start) {
+                    let indent = format!("{}{}", self.indent_of(keyword_element.start), INDENT_STEP);
+                    (keyword_element.end, format!("\n{}{}", indent, value))
+                } else {
+                    (keyword_element.end, format!(" {}", value))
+                }
+            }
+            None => {
+                let multi_line = command.elements.iter().any(|e| self.starts_line(e.start));
+                let last_end = command.elements.last().map_or(command.close_paren, |e| e.end);
+                if multi_line {
+                    let keyword_indent = command
+                        .elements
+                        .iter()
+                        .find(|e| SCOPE_KEYWORDS.contains(&self.value(e)) && self.starts_line(e.start))
+                        .map(|e| self.indent_of(e.start))
+                        .unwrap_or_else(|| format!("{}{}", self.indent_of(command.start), INDENT_STEP));
+                    (
+                        last_end,
+                        format!("\n{}{}\n{}{}{}", keyword_indent, keyword, keyword_indent, INDENT_STEP, value),
+                    )
+                } else {
+                    (command.close_paren, format!(" {} {}", keyword, value))
+                }
+            }
+        };
+
+        self.splice(position, position, &insertion)?;
+        if keep_sorted {
+            self.sort_section(id, keyword)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the argument equivalent to `value` from the section started by `keyword`.
+    ///
+    /// Returns false if no such argument exists. An argument on a line of its own is removed
+    /// together with that line (including a trailing comment).
+    pub fn remove_argument(&mut self, id: CommandId, keyword: &str, value: &str) -> Result<bool> {
+        let command = self.command(id).clone();
+        let Some(section) = self.section_elements(id, keyword) else {
+            return Ok(false);
+        };
+        let Some(target) = section.iter().find(|e| same_argument(self.value(e), value)).cloned() else {
+            return Ok(false);
+        };
+
+        let line_start = self.line_start(target.start);
+        let line_end = self.text[target.end..].find('\n').map_or(self.text.len(), |i| target.end + i);
+        let rest_of_line = self.text[target.end..line_end].trim();
+        let alone_on_line = self.starts_line(target.start)
+            && (rest_of_line.is_empty() || rest_of_line.starts_with('#'))
+            && line_end < self.text.len();
+
+        if alone_on_line {
+            self.splice(line_start, line_end + 1, "")?;
+        } else {
+            // Remove the argument and the whitespace separating it from the previous element
+            let previous_end = command
+                .elements
+                .iter()
+                .take_while(|e| e.end <= target.start)
+                .last()
+                .map_or(target.start, |e| e.end);
+            self.splice(previous_end, target.end, "")?;
+        }
+        Ok(true)
+    }
+
+    /// Inserts the command `text` on its own line before the command `before`, or at the end
+    /// of the file if `before` is `None`.
+    pub fn insert_command(&mut self, before: Option<CommandId>, text: &str) -> Result<()> {
+        match before {
+            Some(id) => {
+                let start = self.command(id).start;
+                let line_start = self.line_start(start);
+                let insertion = format!("{}{}\n", self.indent_of(start), text);
+                self.splice(line_start, line_start, &insertion)
+            }
+            None => {
+                let end = self.text.len();
+                let insertion = if self.text.is_empty() || self.text.ends_with('\n') {
+                    format!("{}\n", text)
+                } else {
+                    format!("\n{}\n", text)
+                };
+                self.splice(end, end, &insertion)
+            }
+        }
+    }
+
+    /// Removes the command `id`, together with its line if nothing else is on it.
+    pub fn remove_command(&mut self, id: CommandId) -> Result<()> {
+        let command = self.command(id).clone();
+        let end = command.close_paren + 1;
+        let line_start = self.line_start(command.start);
+        let line_end = self.text[end..].find('\n').map_or(self.text.len(), |i| end + i);
+        let rest_of_line = self.text[end..line_end].trim();
+
+        if self.starts_line(command.start) && (rest_of_line.is_empty() || rest_of_line.starts_with('#')) {
+            self.splice(line_start, (line_end + 1).min(self.text.len()), "")
+        } else {
+            self.splice(command.start, end, "")
+        }
+    }
+
+    /// Replaces the commands `ids` with `text`: the first of them in file order is replaced in
+    /// place (together with a trailing comment) and the others are removed.
+    pub fn replace_commands(&mut self, ids: &[CommandId], text: &str) -> Result<()> {
+        let mut ids = ids.to_vec();
+        ids.sort_by_key(|id| id.0);
+        ids.dedup();
+        let Some((first, rest)) = ids.split_first() else {
+            return Ok(());
+        };
+
+        // Remove from the back so earlier ids stay valid
+        for id in rest.iter().rev() {
+            self.remove_command(*id)?;
+        }
+        let command = self.command(*first).clone();
+        let end = command.close_paren + 1;
+        let line_end = self.text[end..].find('\n').map_or(self.text.len(), |i| end + i);
+        let rest_of_line = self.text[end..line_end].trim();
+        if rest_of_line.starts_with('#') {
+            self.splice(command.start, line_end, text)
+        } else {
+            self.splice(command.start, end, text)
+        }
+    }
+
+    /// Sorts the arguments of the section started by `keyword`, keeping the layout. A comment
+    /// at the end of an argument's line moves with the argument.
+    pub fn sort_section(&mut self, id: CommandId, keyword: &str) -> Result<()> {
+        let Some(section) = self.section_elements(id, keyword) else {
+            return Ok(());
+        };
+        let spans: Vec<(usize, usize)> =
+            section.iter().map(|element| (element.start, self.line_comment_end(element.end))).collect();
+        let mut units: Vec<(&str, &str)> = section
+            .iter()
+            .zip(&spans)
+            .map(|(element, &(start, end))| (self.value(element), &self.text[start..end]))
+            .collect();
+        units.sort_by(|a, b| compare_arguments(a.0, b.0));
+
+        // Replace from the back so earlier offsets stay valid
+        let mut text = self.text.clone();
+        for (&(start, end), (_, unit)) in spans.iter().zip(&units).rev() {
+            text.replace_range(start..end, unit);
+        }
+        *self = Self::parse(&text)?;
+        Ok(())
+    }
+
+    fn command(&self, id: CommandId) -> &Command {
+        &self.commands[id.0]
+    }
+
+    fn value(&self, element: &Element) -> &str {
+        &self.text[element.start..element.end]
+    }
+
+    fn arguments<'a>(&'a self, command: &'a Command) -> impl Iterator<Item = &'a Element> + 'a {
+        command.elements.iter().filter(|e| e.kind == ElementKind::Argument)
+    }
+
+    fn keyword_element(&self, command: &Command, keyword: &str) -> Option<Element> {
+        self.arguments(command).find(|e| self.value(e) == keyword).cloned()
+    }
+
+    /// Arguments following `keyword` up to the next scope keyword, or `None` if the keyword
+    /// doesn't appear in the command.
+    fn section_elements(&self, id: CommandId, keyword: &str) -> Option<Vec<Element>> {
+        let command = self.command(id);
+        let mut arguments = self.arguments(command).skip_while(|e| self.value(e) != keyword);
+        arguments.next()?;
+        Some(
+            arguments
+                .take_while(|e| !SCOPE_KEYWORDS.contains(&self.value(e)))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    fn line_start(&self, pos: usize) -> usize {
+        self.text[..pos].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// True if only whitespace precedes `pos` on its line.
+    fn starts_line(&self, pos: usize) -> bool {
+        self.text[self.line_start(pos)..pos].trim().is_empty()
+    }
+
+    /// The end of a line comment following `pos` on its line, or `pos` if there is none.
+    fn line_comment_end(&self, pos: usize) -> usize {
+        let line_end = self.text[pos..].find('\n').map_or(self.text.len(), |i| pos + i);
+        let rest = self.text[pos..line_end].trim_start();
+        if rest.starts_with('#') && !rest.starts_with("#[") {
+            line_end
+        } else {
+            pos
+        }
+    }
+
+    fn indent_of(&self, pos: usize) -> String {
+        let line = &self.text[self.line_start(pos)..];
+        line[..line.len() - line.trim_start_matches([' ', '\t']).len()].to_string()
+    }
+
+    fn splice(&mut self, start: usize, end: usize, replacement: &str) -> Result<()> {
+        let mut text = std::mem::take(&mut self.text);
+        text.replace_range(start..end, replacement);
+        *self = Self::parse(&text)?;
+        Ok(())
+    }
+}
+
+/// Compares two arguments, treating quoted/unquoted forms and `${CMAKE_CURRENT_SOURCE_DIR}/`
+/// or `./` prefixes as equivalent.
+pub fn same_argument(a: &str, b: &str) -> bool {
+    normalize_argument(a) == normalize_argument(b)
+}
+
+fn normalize_argument(value: &str) -> &str {
+    let value = unquote(value);
+    ["${CMAKE_CURRENT_SOURCE_DIR}/", "${CMAKE_CURRENT_LIST_DIR}/", "./"]
+        .iter()
+        .find_map(|prefix| value.strip_prefix(prefix))
+        .unwrap_or(value)
+}
+
+fn compare_arguments(a: &str, b: &str) -> Ordering {
+    let (a, b) = (normalize_argument(a), normalize_argument(b));
+    a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| a.cmp(b))
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Tokenizer for the subset of the CMake language needed to edit command arguments.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn parse_file(mut self) -> Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        while let Some(c) = self.peek() {
+            match c {
+                c if c.is_whitespace() => self.pos += c.len_utf8(),
+                '#' => {
+                    self.comment()?;
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => commands.push(self.command()?),
+                other => anyhow::bail!("Unexpected '{}' at line {}", other, self.line()),
+            }
+        }
+        Ok(commands)
+    }
+
+    fn command(&mut self) -> Result<Command> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        let name = self.text[start..self.pos].to_string();
+
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+            self.pos += 1;
+        }
+        if self.peek() != Some('(') {
+            anyhow::bail!("Expected '(' after '{}' at line {}", name, self.line());
+        }
+        self.pos += 1;
+
+        let mut elements = Vec::new();
+        let mut depth = 0;
+        loop {
+            let Some(c) = self.peek() else {
+                anyhow::bail!("Unterminated call to '{}' starting at line {}", name, line_of(self.text, start));
+            };
+            match c {
+                c if c.is_whitespace() => self.pos += c.len_utf8(),
+                '#' => {
+                    let (start, end) = self.comment()?;
+                    elements.push(Element { kind: ElementKind::Comment, start, end });
+                }
+                ')' if depth == 0 => {
+                    let close_paren = self.pos;
+                    self.pos += 1;
+                    return Ok(Command { name, start, close_paren, elements });
+                }
+                '(' | ')' => {
+                    depth += if c == '(' { 1 } else { -1 };
+                    elements.push(Element { kind: ElementKind::Argument, start: self.pos, end: self.pos + 1 });
+                    self.pos += 1;
+                }
+                _ => {
+                    let start = self.pos;
+                    self.argument()?;
+                    elements.push(Element { kind: ElementKind::Argument, start, end: self.pos });
+                }
+            }
+        }
+    }
+
+    /// Consumes a quoted, bracket or unquoted argument.
+    fn argument(&mut self) -> Result<()> {
+        if self.peek() == Some('[') {
+            if let Some(end) = self.bracket_end(self.pos) {
+                self.pos = end;
+                return Ok(());
+            }
+        }
+
+        // Unquoted arguments may embed quoted parts, e.g. NAME="$<TARGET_PROPERTY:...>"
+        while let Some(c) = self.peek() {
+            match c {
+                c if c.is_whitespace() || c == '(' || c == ')' || c == '#' => break,
+                '"' => self.quoted()?,
+                '\\' => self.pos += 1 + self.text[self.pos + 1..].chars().next().map_or(0, char::len_utf8),
+                c => self.pos += c.len_utf8(),
+            }
+        }
+        Ok(())
+    }
+
+    fn quoted(&mut self) -> Result<()> {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            match c {
+                '\\' => self.pos += 1 + self.text[self.pos + 1..].chars().next().map_or(0, char::len_utf8),
+                '"' => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                c => self.pos += c.len_utf8(),
+            }
+        }
+        anyhow::bail!("Unterminated string starting at line {}", line_of(self.text, start))
+    }
+
+    /// Consumes a line or bracket comment and returns its byte range.
+    fn comment(&mut self) -> Result<(usize, usize)> {
+        let start = self.pos;
+        if let Some(end) = self.bracket_end(self.pos + 1) {
+            self.pos = end;
+        } else {
+            self.pos = self.text[start..].find('\n').map_or(self.text.len(), |i| start + i);
+        }
+        Ok((start, self.pos))
+    }
+
+    /// If a bracket opening like `[==[` starts at `pos`, returns the end of its closing bracket.
+    fn bracket_end(&self, pos: usize) -> Option<usize> {
+        let rest = self.text.get(pos..)?.strip_prefix('[')?;
+        let equals = rest.len() - rest.trim_start_matches('=').len();
+        rest[equals..].strip_prefix('[')?;
+        let close = format!("]{}]", "=".repeat(equals));
+        let body_start = pos + equals + 2;
+        self.text[body_start..].find(&close).map(|i| body_start + i + close.len())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn line(&self) -> usize {
+        line_of(self.text, self.pos)
+    }
+}
+
+fn line_of(text: &str, pos: usize) -> usize {
+    text[..pos].matches('\n').count() + 1
+}
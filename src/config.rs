@@ -0,0 +1,302 @@
+// src/config.rs
+//! The global configuration file and its settings, as read and written by `jumake config`.
+//!
+//! JUCE installs have their own commands (`jumake juce ...`); `juce_path` is a shortcut for
+//! the default install.
+
+use crate::error::JuMakeError;
+use crate::juce::read_juce_version;
+use crate::manifest::BUILD_TYPES;
+use anyhow::{Context as AnyhowContext, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The keys `jumake config` accepts, with a description each.
+pub const CONFIG_KEYS: &[(&str, &str)] = &[
+    ("juce_path", "JUCE folder of the default install (setting it registers the folder)"),
+    ("default_juce", "Name of the JUCE install used by projects that don't pin one"),
+    ("template_dir", "Additional directory searched for project templates"),
+    ("default_template", "Template for `jumake new` when none is given"),
+    ("author_name", "Author of initial commits and {{author}} when Git has no user.name"),
+    ("author_email", "E-mail address of initial commits when Git has no user.email"),
+    ("company_name", "COMPANY_NAME of new plugins"),
+    ("manufacturer_code", "PLUGIN_MANUFACTURER_CODE of new plugins: four characters, one upper-case"),
+    ("default_generator", "CMake generator for projects that don't set one"),
+    ("default_build_type", "Build type of new projects"),
+];
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct JuMakeConfig {
+    /// The single JUCE folder of older versions; moved into `juce_installs` on load.
+    #[serde(default, skip_serializing)]
+    pub juce_path: Option<PathBuf>,
+    /// Additional directory searched for user-defined project templates.
+    pub template_dir: Option<PathBuf>,
+    /// Name of the JUCE install used by projects that don't pin a version.
+    pub default_juce: Option<String>,
+    /// Author of new projects' initial commits and the `{{author}}` template variable, when Git
+    /// has no `user.name`.
+    pub author_name: Option<String>,
+    /// E-mail address of new projects' initial commits, when Git has no `user.email`.
+    pub author_email: Option<String>,
+    /// Template for `jumake new` when none is given.
+    pub default_template: Option<String>,
+    /// `COMPANY_NAME` of new plugins.
+    pub company_name: Option<String>,
+    /// `PLUGIN_MANUFACTURER_CODE` of new plugins.
+    pub manufacturer_code: Option<String>,
+    /// CMake generator for projects that don't set one in `jumake.toml`.
+    pub default_generator: Option<String>,
+    /// Build type of new projects.
+    pub default_build_type: Option<String>,
+    /// Registered JUCE installs by name.
+    #[serde(default)]
+    pub juce_installs: BTreeMap<String, JuceInstall>,
+}
+
+/// A registered local JUCE folder.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JuceInstall {
+    pub path: PathBuf,
+    /// Version from the folder's `CMakeLists.txt`, read when it was registered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl JuMakeConfig {
+    /// The default JUCE install, or the first one if no default is set.
+    pub fn default_install(&self) -> Option<(&String, &JuceInstall)> {
+        self.default_juce
+            .as_ref()
+            .and_then(|name| self.juce_installs.get_key_value(name))
+            .or_else(|| self.juce_installs.iter().next())
+    }
+
+    /// Finds an install by name, or by version preferring the default install.
+    pub fn find_install(&self, name_or_version: &str) -> Option<(&String, &JuceInstall)> {
+        let has_version = |install: &JuceInstall| install.version.as_deref() == Some(name_or_version);
+        self.juce_installs
+            .get_key_value(name_or_version)
+            .or_else(|| self.default_install().filter(|(_, install)| has_version(install)))
+            .or_else(|| self.juce_installs.iter().find(|(_, install)| has_version(install)))
+    }
+
+    /// Registers the JUCE folder at `path` as `name`, reading its version.
+    pub fn add_install(&mut self, name: &str, path: PathBuf) -> &JuceInstall {
+        let version = read_juce_version(&path);
+        if self.default_juce.is_none() {
+            self.default_juce = Some(name.to_string());
+        }
+        self.juce_installs.entry(name.to_string()).or_insert(JuceInstall { path, version })
+    }
+}
+
+/// Name for a JUCE install registered without one, e.g. `juce-8.0.4`.
+pub(crate) fn install_name(version: Option<&str>) -> String {
+    version.map_or_else(|| "juce".to_string(), |version| format!("juce-{}", version))
+}
+
+/// Returns the location of the global configuration file, creating its directory if needed.
+///
+/// A configuration left in the cache directory by older versions is moved here first.
+pub fn config_file_path() -> Result<PathBuf, JuMakeError> {
+    // Platform-specific config directory
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| JuMakeError::Config("Cannot determine config directory".into()))?
+        .join("jumake");
+
+    fs::create_dir_all(&config_dir)?;
+    let config_file = config_dir.join("config.toml");
+    if !config_file.exists() {
+        migrate_legacy_config(&config_file)?;
+    }
+    Ok(config_file)
+}
+
+/// Moves `config.toml` from the cache directory, where older versions kept it, to `config_file`.
+fn migrate_legacy_config(config_file: &Path) -> Result<(), JuMakeError> {
+    let Some(legacy_file) = dirs::cache_dir().map(|dir| dir.join("jumake").join("config.toml")) else {
+        return Ok(());
+    };
+    if !legacy_file.is_file() || legacy_file == config_file {
+        return Ok(());
+    }
+    // A rename fails across file systems
+    if fs::rename(&legacy_file, config_file).is_err() {
+        fs::copy(&legacy_file, config_file)?;
+        fs::remove_file(&legacy_file)?;
+    }
+    println!("Moved JuMake configuration from {} to {}", legacy_file.display(), config_file.display());
+    Ok(())
+}
+
+/// Loads the global configuration, falling back to defaults if no file exists yet.
+pub fn load_config() -> Result<JuMakeConfig, JuMakeError> {
+    let config_file = config_file_path()?;
+    let mut config: JuMakeConfig = if config_file.exists() {
+        toml::from_str(&fs::read_to_string(&config_file)?)?
+    } else {
+        JuMakeConfig::default()
+    };
+
+    // Older configurations hold a single JUCE folder
+    if let Some(path) = config.juce_path.take() {
+        if config.juce_installs.is_empty() {
+            let name = install_name(read_juce_version(&path).as_deref());
+            config.add_install(&name, path);
+        }
+    }
+    Ok(config)
+}
+
+/// Writes the global configuration atomically to prevent config corruption.
+pub fn save_config(config: &JuMakeConfig) -> Result<(), JuMakeError> {
+    let config_file = config_file_path()?;
+    let tmp_file = config_file.with_extension("tmp");
+    fs::write(&tmp_file, toml::to_string(config)?)?;
+    fs::rename(&tmp_file, &config_file)?;
+    Ok(())
+}
+
+impl JuMakeConfig {
+    /// Value of the setting `key`, or `None` if it is not set.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let path = |path: &Option<PathBuf>| path.as_ref().map(|path| path.display().to_string());
+        Ok(match check_key(key)? {
+            "juce_path" => self.default_install().map(|(_, install)| install.path.display().to_string()),
+            "default_juce" => self.default_juce.clone(),
+            "template_dir" => path(&self.template_dir),
+            "default_template" => self.default_template.clone(),
+            "author_name" => self.author_name.clone(),
+            "author_email" => self.author_email.clone(),
+            "company_name" => self.company_name.clone(),
+            "manufacturer_code" => self.manufacturer_code.clone(),
+            "default_generator" => self.default_generator.clone(),
+            _ => self.default_build_type.clone(),
+        })
+    }
+
+    /// Changes the setting `key` after validating `value`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        if value.is_empty() {
+            anyhow::bail!("Empty value for '{}'; use `jumake config unset {}` instead", key, key);
+        }
+        match check_key(key)? {
+            "juce_path" => {
+                let path = PathBuf::from(value)
+                    .canonicalize()
+                    .with_context(|| format!("JUCE folder {} does not exist", value))?;
+                let version = read_juce_version(&path).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} is not a JUCE folder: no 'project(JUCE VERSION ...)' in its CMakeLists.txt",
+                        value
+                    )
+                })?;
+                let name = match self.juce_installs.iter().find(|(_, install)| install.path == path) {
+                    Some((name, _)) => name.clone(),
+                    None => {
+                        let name = install_name(Some(&version));
+                        if let Some(existing) = self.juce_installs.get(&name) {
+                            anyhow::bail!(
+                                "The JUCE install '{}' already points at {}. Register {} under another name with \
+                                 `jumake juce add <name> <path>` and make it the default with `jumake juce default <name>`",
+                                name,
+                                existing.path.display(),
+                                path.display()
+                            );
+                        }
+                        self.add_install(&name, path);
+                        name
+                    }
+                };
+                self.default_juce = Some(name);
+            }
+            "default_juce" => {
+                if !self.juce_installs.contains_key(value) {
+                    anyhow::bail!("No JUCE install named '{}'", value);
+                }
+                self.default_juce = Some(value.to_string());
+            }
+            "template_dir" => {
+                let path = PathBuf::from(value);
+                if !path.is_dir() {
+                    anyhow::bail!("Template directory {} does not exist", path.display());
+                }
+                self.template_dir = Some(path);
+            }
+            "company_name" => {
+                // Rendered into a quoted CMake argument and, by JUCE, into a C++ string literal
+                if value.chars().any(|c| matches!(c, '"' | '\\' | ';' | '$') || c.is_control()) {
+                    anyhow::bail!(
+                        "Invalid company name '{}': quotes, backslashes, semicolons and '$' are not allowed",
+                        value
+                    );
+                }
+                self.company_name = Some(value.to_string());
+            }
+            "manufacturer_code" => {
+                if value.len() != 4
+                    || !value.chars().all(|c| c.is_ascii_alphanumeric())
+                    || !value.chars().any(|c| c.is_ascii_uppercase())
+                {
+                    anyhow::bail!(
+                        "Invalid manufacturer code '{}': expected four letters or digits, at least one upper-case",
+                        value
+                    );
+                }
+                self.manufacturer_code = Some(value.to_string());
+            }
+            "default_build_type" => {
+                if !BUILD_TYPES.contains(&value) {
+                    anyhow::bail!("Invalid build type: {}. Valid options: {}", value, BUILD_TYPES.join(", "));
+                }
+                self.default_build_type = Some(value.to_string());
+            }
+            other => {
+                if let Some(setting) = self.string_setting(other) {
+                    *setting = Some(value.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the setting `key`.
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        match check_key(key)? {
+            "juce_path" => anyhow::bail!("Unregister JUCE installs with `jumake juce remove <name>`"),
+            "default_juce" => self.default_juce = None,
+            "template_dir" => self.template_dir = None,
+            other => {
+                if let Some(setting) = self.string_setting(other) {
+                    *setting = None;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The settings stored as plain strings.
+    fn string_setting(&mut self, key: &str) -> Option<&mut Option<String>> {
+        match key {
+            "default_template" => Some(&mut self.default_template),
+            "author_name" => Some(&mut self.author_name),
+            "author_email" => Some(&mut self.author_email),
+            "company_name" => Some(&mut self.company_name),
+            "manufacturer_code" => Some(&mut self.manufacturer_code),
+            "default_generator" => Some(&mut self.default_generator),
+            "default_build_type" => Some(&mut self.default_build_type),
+            _ => None,
+        }
+    }
+}
+
+/// Returns `key` if it is one of [`CONFIG_KEYS`].
+fn check_key(key: &str) -> Result<&'static str> {
+    CONFIG_KEYS.iter().map(|(known, _)| *known).find(|known| *known == key).ok_or_else(|| {
+        let keys: Vec<&str> = CONFIG_KEYS.iter().map(|(key, _)| *key).collect();
+        anyhow::anyhow!("Unknown configuration key '{}'. Valid keys: {}", key, keys.join(", "))
+    })
+}
@@ -1,4 +1,5 @@
 // src/context.rs
+use crate::manifest::ProjectManifest;
 use std::path::PathBuf;
 
 
@@ -7,4 +8,22 @@ pub struct Context {
     pub project_path: PathBuf,
     pub template_name: Option<String>,
     pub build_type: String,
+    /// Settings from `jumake.toml`.
+    pub manifest: ProjectManifest,
+}
+
+impl Context {
+    /// Builds the context of the project at `project_path` from its `jumake.toml`.
+    ///
+    /// The build type is the manifest's default; callers override it for a single command.
+    pub fn load(project_path: PathBuf) -> anyhow::Result<Self> {
+        let manifest = ProjectManifest::load(&project_path)?;
+        Ok(Self {
+            project_name: manifest.project.name.clone(),
+            project_path,
+            template_name: manifest.project.template.clone(),
+            build_type: manifest.build.build_type.clone(),
+            manifest,
+        })
+    }
 }
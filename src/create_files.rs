@@ -2,155 +2,246 @@
 //! This module provides functions to create source files and CMakeLists for projects
 //! and to add new classes or components based on templates.
 
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
-use std::path::{Path};
+use std::fs;
+use std::path::{Path, PathBuf};
 use indoc::indoc;
+use crate::cmake_edit::CMakeDocument;
 use crate::context::Context;
+use crate::error::JuMakeError;
+use crate::juce::juce_cmake_lines;
+use crate::placeholders::{is_identifier, render, standard_variables, Variables};
+use crate::templates::TemplateRegistry;
 use anyhow::{Context as AnyhowContext, Result};
 
 /// Creates source files in the project based on the template specified in the context.
+///
+/// The template is looked up in the [`TemplateRegistry`], so both built-in and
+/// user-defined templates are supported.
 pub fn create_source_files(context: &Context) -> Result<()> {
-    let src_path = context.project_path.join("src");
+    let template_name = context
+        .template_name
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("No template specified in the context"))?;
 
-    // Ensure the `src` directory exists
-    fs::create_dir_all(&src_path)
-        .with_context(|| format!("Failed to create directory: {}", src_path.display()))?;
+    let registry = TemplateRegistry::load()?;
+    let template = registry.get(template_name).ok_or_else(|| {
+        JuMakeError::Invalid(format!(
+            "Unknown template: {}. Available templates: {}",
+            template_name,
+            registry.names().join(", ")
+        ))
+    })?;
 
-    match context.template_name.as_deref() {
-        Some("GuiApplication") => {
-            create_file_from_template(&src_path, "Main.cpp", MAIN_CPP_TEMPLATE)?;
-            create_file_from_template(&src_path, "MainComponent.cpp", MAIN_COMPONENT_CPP_TEMPLATE)?;
-            create_file_from_template(&src_path, "MainComponent.h", MAIN_COMPONENT_H_TEMPLATE)?;
-            create_file_from_template(&src_path, "CMakeLists.txt", GUI_APP_CMAKE_TEMPLATE)?;
-        }
-        Some("AudioPlugin") => {
-            create_file_from_template(&src_path, "PluginProcessor.cpp", PLUGIN_PROCESSOR_CPP_TEMPLATE)?;
-            create_file_from_template(&src_path, "PluginProcessor.h", PLUGIN_PROCESSOR_H_TEMPLATE)?;
-            create_file_from_template(&src_path, "PluginEditor.cpp", PLUGIN_EDITOR_CPP_TEMPLATE)?;
-            create_file_from_template(&src_path, "PluginEditor.h", PLUGIN_EDITOR_H_TEMPLATE)?;
-            create_file_from_template(&src_path, "CMakeLists.txt", AUDIO_PLUGIN_CMAKE_TEMPLATE)?;
-        }
-        Some("ConsoleApp") => {
-            create_file_from_template(&src_path, "Main.cpp", CONSOLE_APP_MAIN_CPP_TEMPLATE)?;
-            create_file_from_template(&src_path, "CMakeLists.txt", CONSOLE_APP_CMAKE_TEMPLATE)?;
-        }
-        Some(template) => anyhow::bail!("Unknown template: {}", template),
-        None => anyhow::bail!("No template specified in the context"),
-    }
-
-    Ok(())
+    template.instantiate(context)
 }
 
-/// Adds a new class or component to the project.
-pub fn add_class(context: &Context, element_type: &str, element_name: &str) -> Result<()> {
+/// Adds a new class, component or other JUCE helper class to the project.
+///
+/// Every element type except `class` appends its suffix to the name (e.g. `Filter` →
+/// `FilterProcessor`) unless the name already ends with it.
+///
+/// `element_name` may contain a relative directory below `src/` (e.g. `dsp/Filter`); the
+/// directory is created if needed and the source file is registered in the nearest
+/// `CMakeLists.txt`. If `namespace` is given, the class is wrapped in that namespace.
+pub fn add_class(
+    context: &Context,
+    element_type: &str,
+    element_name: &str,
+    namespace: Option<&str>,
+) -> Result<()> {
     let src_path = context.project_path.join("src");
+    let (sub_dir, element_name) = split_element_path(element_name)?;
+    let target_dir = src_path.join(&sub_dir);
 
-    // Determine templates and adjusted name
-    let (header_template, cpp_template, adjusted_name) = match element_type {
-        "class" => (CLASS_H_TEMPLATE, CLASS_CPP_TEMPLATE, element_name.to_string()),
-        "component" => {
-            let mut name = element_name.to_string();
-            name.push_str("Component");
-            (COMPONENT_H_TEMPLATE, COMPONENT_CPP_TEMPLATE, name)
-        }
-        _ => anyhow::bail!("Invalid element type: {}", element_type),
+    if let Some(namespace) = namespace {
+        validate_namespace(namespace)?;
+    }
+
+    // Determine templates, name suffix and the JUCE module the new class depends on
+    let (header_template, cpp_template, suffix, required_module) = match element_type {
+        "class" => (CLASS_H_TEMPLATE, CLASS_CPP_TEMPLATE, "", None),
+        "component" => (COMPONENT_H_TEMPLATE, COMPONENT_CPP_TEMPLATE, "Component", None),
+        "processor" => (PROCESSOR_H_TEMPLATE, PROCESSOR_CPP_TEMPLATE, "Processor", Some("juce_dsp")),
+        "editor" => (EDITOR_H_TEMPLATE, EDITOR_CPP_TEMPLATE, "Editor", None),
+        "look-and-feel" => (LOOK_AND_FEEL_H_TEMPLATE, LOOK_AND_FEEL_CPP_TEMPLATE, "LookAndFeel", None),
+        "thread" => (THREAD_H_TEMPLATE, THREAD_CPP_TEMPLATE, "Thread", None),
+        "timer" => (TIMER_H_TEMPLATE, TIMER_CPP_TEMPLATE, "Timer", None),
+        _ => anyhow::bail!(JuMakeError::Invalid(format!("Invalid element type: {}", element_type))),
+    };
+
+    let adjusted_name = if element_name.ends_with(suffix) {
+        element_name.to_string()
+    } else {
+        format!("{}{}", element_name, suffix)
     };
 
     let header_file_name = format!("{}.h", adjusted_name);
     let cpp_file_name = format!("{}.cpp", adjusted_name);
-    let header_path = src_path.join(&header_file_name);
-    let cpp_path = src_path.join(&cpp_file_name);
+    let header_path = target_dir.join(&header_file_name);
+    let cpp_path = target_dir.join(&cpp_file_name);
 
     // Prevent overwriting existing files
     if header_path.exists() || cpp_path.exists() {
-        anyhow::bail!("{} '{}' already exists in the project.", element_type, adjusted_name);
+        anyhow::bail!(JuMakeError::Invalid(format!(
+            "{} '{}' already exists in the project.",
+            element_type,
+            adjusted_name
+        )));
     }
 
-    // Create files from templates
-    create_classfile_from_template(&src_path, &header_file_name, header_template, &adjusted_name)?;
-    create_classfile_from_template(&src_path, &cpp_file_name, cpp_template, &adjusted_name)?;
+    // Render the files and edit the CMakeLists.txt closest to the new file (using a path
+    // relative to it) in memory, so that nothing is written if any of it fails
+    let mut variables = standard_variables(&context.project_name);
+    variables.insert("class_name".to_string(), adjusted_name.clone());
+    variables.insert("namespace".to_string(), namespace.unwrap_or_default().to_string());
+    let header = render_class_template(&header_file_name, header_template, &variables)?;
+    let cpp = render_class_template(&cpp_file_name, cpp_template, &variables)?;
+    let (cmake_dir, cpp_entry) = cmake_source_entry(&src_path, &cpp_path);
+    let cmakelists_path = cmake_dir.join("CMakeLists.txt");
+    let cmakelists = fs::read_to_string(&cmakelists_path)
+        .with_context(|| format!("Failed to open CMakeLists.txt at {}", cmakelists_path.display()))?;
+    let cmakelists = add_cmake_source(&cmakelists, &cpp_entry)
+        .with_context(|| format!("Failed to update CMakeLists.txt at {}", cmakelists_path.display()))?;
 
-    // Update CMakeLists.txt
-    update_cmakelists(&src_path, &cpp_file_name)?;
+    fs::create_dir_all(&target_dir)
+        .with_context(|| format!("Failed to create directory: {}", target_dir.display()))?;
+    for (path, content) in [(&header_path, header), (&cpp_path, cpp)] {
+        fs::write(path, content).with_context(|| format!("Failed to create file: {}", path.display()))?;
+        println!("Created file: {}", path.display());
+    }
+    fs::write(&cmakelists_path, cmakelists)
+        .with_context(|| format!("Failed to update CMakeLists.txt at {}", cmakelists_path.display()))?;
 
     println!("{} '{}' added successfully!", element_type, adjusted_name);
+
+    if let Some(module) = required_module {
+        let cmakelists = fs::read_to_string(src_path.join("CMakeLists.txt")).unwrap_or_default();
+        if !cmakelists.contains(module) {
+            println!(
+                "Note: '{}' requires juce::{} — link it with `jumake module add {}`.",
+                adjusted_name, module, module
+            );
+        }
+    }
     Ok(())
 }
 
-/// Creates a file from a template, replacing "Template" with `element_name`.
-fn create_classfile_from_template(
-    src_path: &Path,
-    file_name: &str,
-    template: &[u8],
-    element_name: &str,
-) -> Result<()> {
-    let path = src_path.join(file_name);
-    let content = String::from_utf8_lossy(template).replace("Template", element_name);
-    fs::write(&path, content.as_bytes())
-        .with_context(|| format!("Failed to create file: {}", path.display()))?;
-    println!("Created file: {}", path.display());
-    Ok(())
+/// Splits `dsp/Filter` into the directory below `src/` and the class name.
+pub(crate) fn split_element_path(element_name: &str) -> Result<(PathBuf, &str)> {
+    let (dir, name) = element_name.rsplit_once('/').unwrap_or(("", element_name));
+
+    let mut sub_dir = PathBuf::new();
+    for part in dir.split('/').filter(|part| !part.is_empty()) {
+        if part == "." || part == ".." || part.contains('\\') {
+            anyhow::bail!(JuMakeError::Invalid(format!("Invalid directory '{}' in '{}'", part, element_name)));
+        }
+        sub_dir.push(part);
+    }
+
+    if !is_identifier(name) {
+        anyhow::bail!(JuMakeError::Invalid(format!("'{}' is not a valid C++ class name", name)));
+    }
+    Ok((sub_dir, name))
 }
 
-/// Creates a file from a template without modifications.
-fn create_file_from_template(src_path: &Path, file_name: &str, template: &[u8]) -> Result<()> {
-    let path = src_path.join(file_name);
-    fs::write(&path, template)
-        .with_context(|| format!("Failed to create file: {}", path.display()))?;
-    println!("Created file: {}", path.display());
-    Ok(())
+/// Validates a (possibly nested, `a::b`) C++ namespace.
+fn validate_namespace(namespace: &str) -> Result<()> {
+    if namespace.split("::").all(is_identifier) {
+        Ok(())
+    } else {
+        anyhow::bail!(JuMakeError::Invalid(format!("'{}' is not a valid C++ namespace", namespace)))
+    }
 }
 
-/// Updates `CMakeLists.txt` to include the newly created cpp file under `PRIVATE`.
-fn update_cmakelists(src_path: &Path, cpp_file_name: &str) -> Result<()> {
-    let cmakelists_path = src_path.join("CMakeLists.txt");
-    let file = File::open(&cmakelists_path)
-        .with_context(|| format!("Failed to open CMakeLists.txt at {}", cmakelists_path.display()))?;
-    let reader = BufReader::new(file);
-    let lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
+/// Returns the directory of the `CMakeLists.txt` closest to `dir`, searching upwards
+/// but never leaving `src_path`.
+fn nearest_cmakelists_dir(src_path: &Path, dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .take_while(|ancestor| ancestor.starts_with(src_path))
+        .find(|ancestor| ancestor.join("CMakeLists.txt").is_file())
+        .unwrap_or(src_path)
+        .to_path_buf()
+}
 
-    let mut new_lines = Vec::with_capacity(lines.len() + 1);
-    let mut found_target_sources = false;
-    let mut added = false;
+/// Returns the directory of the `CMakeLists.txt` responsible for `file` and the
+/// `/`-separated path of `file` relative to that directory.
+pub(crate) fn cmake_source_entry(src_path: &Path, file: &Path) -> (PathBuf, String) {
+    let dir = file.parent().unwrap_or(src_path);
+    let cmake_dir = nearest_cmakelists_dir(src_path, dir);
+    let entry = file
+        .strip_prefix(&cmake_dir)
+        .unwrap_or(file)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    (cmake_dir, entry)
+}
 
-    for line in &lines {
-        new_lines.push(line.clone());
+/// Renders a class file template with `variables`.
+fn render_class_template(file_name: &str, template: &[u8], variables: &Variables) -> Result<String> {
+    render(&String::from_utf8_lossy(template), variables)
+        .with_context(|| format!("Failed to render template for {}", file_name))
+}
 
-        if line.trim_start().starts_with("target_sources(${PROJECT_NAME}") {
-            found_target_sources = true;
-        }
+/// Returns `content` with `cpp_file_name` added to the `PRIVATE` sources of
+/// `target_sources(${PROJECT_NAME} ...)`.
+///
+/// If the existing sources are written as `${CMAKE_CURRENT_SOURCE_DIR}/...`, the new entry
+/// follows that style.
+pub(crate) fn add_cmake_source(content: &str, cpp_file_name: &str) -> Result<String> {
+    let mut document = CMakeDocument::parse(content)?;
+    let target_sources = document
+        .find_command("target_sources", Some("${PROJECT_NAME}"))
+        .ok_or_else(|| anyhow::anyhow!("Could not find 'target_sources(${{PROJECT_NAME}}' in CMakeLists.txt"))?;
 
-        if found_target_sources && line.trim_start().starts_with("PRIVATE") && !added {
-            // Calculate indentation and insert the cpp file
-            let indentation = line.chars().take_while(|c| c.is_whitespace()).count() + 4;
-            let new_cpp_line = format!("{:indent$}{}", "", cpp_file_name, indent = indentation);
-            new_lines.push(new_cpp_line);
-            added = true;
-        }
+    if document.contains(target_sources, "PRIVATE", cpp_file_name) {
+        anyhow::bail!(JuMakeError::Invalid(format!("'{}' is already listed in target_sources", cpp_file_name)));
     }
 
-    if !added {
-        anyhow::bail!("Could not find 'PRIVATE' after 'target_sources' in CMakeLists.txt");
-    }
+    let sources = document.section(target_sources, "PRIVATE");
+    let prefix = "${CMAKE_CURRENT_SOURCE_DIR}/";
+    let entry = if !sources.is_empty() && sources.iter().all(|s| s.starts_with(prefix)) {
+        format!("{}{}", prefix, cpp_file_name)
+    } else {
+        cpp_file_name.to_string()
+    };
 
-    fs::write(&cmakelists_path, new_lines.join("\n"))
-        .with_context(|| format!("Failed to update CMakeLists.txt at {}", cmakelists_path.display()))?;
-    Ok(())
+    document.insert_argument(target_sources, "PRIVATE", &entry)?;
+    Ok(document.into_string())
+}
+
+/// Returns `content` with the `target_sources` entry `cpp_file_name` removed.
+pub(crate) fn remove_cmake_source(content: &str, cpp_file_name: &str) -> Result<String> {
+    let mut document = CMakeDocument::parse(content)?;
+    let target_sources = document
+        .find_command("target_sources", Some("${PROJECT_NAME}"))
+        .ok_or_else(|| anyhow::anyhow!("Could not find 'target_sources(${{PROJECT_NAME}}' in CMakeLists.txt"))?;
+
+    if !document.remove_argument(target_sources, "PRIVATE", cpp_file_name)? {
+        anyhow::bail!(JuMakeError::Invalid(format!("'{}' is not listed in target_sources", cpp_file_name)));
+    }
+    Ok(document.into_string())
 }
 
+/// CMake version required by the generated `CMakeLists.txt`.
+pub const CMAKE_MINIMUM_VERSION: &str = "3.24";
+
 /// Creates a basic `CMakeLists.txt` for the project.
 pub fn create_cmakelists(context: &Context) -> Result<()> {
     let cmakelists_path = context.project_path.join("CMakeLists.txt");
 
     let cmake_content = format!(
         indoc! {"
-            cmake_minimum_required(VERSION 3.24)
-            project({} VERSION 0.0.1)
-            add_subdirectory(modules/JUCE)
+            cmake_minimum_required(VERSION {})
+            project({} VERSION {})
+            {}
             add_subdirectory(src)
         "},
-        context.project_name
+        CMAKE_MINIMUM_VERSION,
+        context.project_name,
+        context.manifest.project.version,
+        juce_cmake_lines(&context.manifest.juce)
     );
 
     fs::write(&cmakelists_path, cmake_content.as_bytes())
@@ -159,21 +250,17 @@ pub fn create_cmakelists(context: &Context) -> Result<()> {
 }
 
 // ======================= TEMPLATES ========================
-const MAIN_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/GuiApplicationTemplate/Main.cpp.template");
-const MAIN_COMPONENT_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/GuiApplicationTemplate/MainComponent.cpp.template");
-const MAIN_COMPONENT_H_TEMPLATE: &[u8] = include_bytes!("../templates/GuiApplicationTemplate/MainComponent.h.template");
-const GUI_APP_CMAKE_TEMPLATE: &[u8] = include_bytes!("../templates/GuiApplicationTemplate/CMakeLists.txt.template");
-
-const PLUGIN_PROCESSOR_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/AudioPluginTemplate/PluginProcessor.cpp.template");
-const PLUGIN_PROCESSOR_H_TEMPLATE: &[u8] = include_bytes!("../templates/AudioPluginTemplate/PluginProcessor.h.template");
-const PLUGIN_EDITOR_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/AudioPluginTemplate/PluginEditor.cpp.template");
-const PLUGIN_EDITOR_H_TEMPLATE: &[u8] = include_bytes!("../templates/AudioPluginTemplate/PluginEditor.h.template");
-const AUDIO_PLUGIN_CMAKE_TEMPLATE: &[u8] = include_bytes!("../templates/AudioPluginTemplate/CMakeLists.txt.template");
-
-const CONSOLE_APP_MAIN_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ConsoleAppTemplate/Main.cpp.template");
-const CONSOLE_APP_CMAKE_TEMPLATE: &[u8] = include_bytes!("../templates/ConsoleAppTemplate/CMakeLists.txt.template");
-
 const CLASS_H_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Class.h.template");
 const CLASS_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Class.cpp.template");
 const COMPONENT_H_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Component.h.template");
 const COMPONENT_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Component.cpp.template");
+const PROCESSOR_H_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Processor.h.template");
+const PROCESSOR_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Processor.cpp.template");
+const EDITOR_H_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Editor.h.template");
+const EDITOR_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Editor.cpp.template");
+const LOOK_AND_FEEL_H_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/LookAndFeel.h.template");
+const LOOK_AND_FEEL_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/LookAndFeel.cpp.template");
+const THREAD_H_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Thread.h.template");
+const THREAD_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Thread.cpp.template");
+const TIMER_H_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Timer.h.template");
+const TIMER_CPP_TEMPLATE: &[u8] = include_bytes!("../templates/ClassTemplates/Timer.cpp.template");
@@ -1,31 +1,40 @@
 // src/create_project.rs
-//! This module provides functionality to create a new project directory, 
+//! This module provides functionality to create a new project directory,
 //! set up source files, generate CMakeLists, and initialize Git with an initial commit.
+//!
+//! The project is assembled in a hidden staging directory next to its destination and only
+//! renamed into place once every step succeeded, so a failed `jumake new` leaves nothing behind.
 
 use crate::context::Context;
 use crate::create_files::{create_cmakelists, create_source_files};
-use crate::initialize_git::{create_initial_commit, initialize_git_repo, JuMakeError};
+use crate::error::JuMakeError;
+use crate::initialize_git::{create_initial_commit, initialize_git_repo};
+use crate::manifest::MANIFEST_FILE_NAME;
+use crate::presets::{write_presets, PRESETS_FILE};
+use std::error::Error;
 use std::fs;
+use std::path::PathBuf;
 use log::{info, warn};
 
 /// Creates a new project directory and sets up the project structure.
 ///
-/// Steps performed:
+/// Steps performed, in a staging directory:
 /// 1. Creates the project directory.
-/// 2. Generates `CMakeLists.txt`.
-/// 3. Creates source files based on template.
-/// 4. Initializes Git repository.
-/// 5. Creates initial commit.
+/// 2. Writes the `jumake.toml` manifest.
+/// 3. Generates `CMakeLists.txt`.
+/// 4. Generates `CMakePresets.json`.
+/// 5. Creates source files based on template.
+/// 6. Initializes Git repository.
+/// 7. Creates the initial commit with `commit_message`, unless it is `None`.
+///
+/// The staging directory is then renamed to the project path, or removed if a step failed.
 ///
 /// # Errors
-/// Returns a `JuMakeError` if the project directory already exists or on any I/O error.
-pub fn create_project(context: &Context) -> Result<(), JuMakeError> {
+/// Returns a `JuMakeError` if the project directory already exists or if any step fails.
+pub fn create_project(context: &Context, commit_message: Option<&str>) -> Result<(), JuMakeError> {
     // Check if project directory already exists
     if context.project_path.exists() {
-        return Err(JuMakeError::Config(format!(
-            "Project directory already exists: {}",
-            context.project_path.display()
-        )));
+        return Err(JuMakeError::ProjectExists(context.project_path.clone()));
     }
 
     info!(
@@ -34,28 +43,25 @@ pub fn create_project(context: &Context) -> Result<(), JuMakeError> {
         context.project_path.display()
     );
 
-    // Step 1: Create project directory
-    fs::create_dir_all(&context.project_path).map_err(JuMakeError::Io)?;
-
-    // Step 2: Create CMakeLists.txt with robust error logging
-    if let Err(e) = create_cmakelists(context) {
-        // Using warn! instead of panicking keeps CLI flow uninterrupted
-        warn!("Failed to create CMakeLists.txt: {}", e);
+    let staging = Context {
+        project_name: context.project_name.clone(),
+        project_path: staging_path(context)?,
+        template_name: context.template_name.clone(),
+        build_type: context.build_type.clone(),
+        manifest: context.manifest.clone(),
+    };
+    if staging.project_path.exists() {
+        // Left over from an interrupted run
+        fs::remove_dir_all(&staging.project_path)?;
     }
 
-    // Step 3: Create source files
-    if let Err(e) = create_source_files(context) {
-        warn!("Failed to create source files: {}", e);
-    }
-
-    // Step 4: Initialize Git repository
-    if let Err(e) = initialize_git_repo(context) {
-        warn!("Failed to initialize Git repository: {:?}", e);
-    }
-
-    // Step 5: Create initial commit
-    if let Err(e) = create_initial_commit(context) {
-        warn!("Failed to create initial commit: {:?}", e);
+    let result = create_project_files(&staging, commit_message)
+        .and_then(|()| fs::rename(&staging.project_path, &context.project_path).map_err(JuMakeError::Io));
+    if let Err(e) = result {
+        if let Err(cleanup_error) = fs::remove_dir_all(&staging.project_path) {
+            warn!("Failed to remove {}: {}", staging.project_path.display(), cleanup_error);
+        }
+        return Err(e);
     }
 
     info!(
@@ -66,3 +72,50 @@ pub fn create_project(context: &Context) -> Result<(), JuMakeError> {
 
     Ok(())
 }
+
+/// Hidden directory next to the project path, on the same file system so that the final
+/// rename is atomic. Creates the parent directory if needed.
+fn staging_path(context: &Context) -> Result<PathBuf, JuMakeError> {
+    let parent = match context.project_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&parent)?;
+    let name = context
+        .project_path
+        .file_name()
+        .map_or_else(|| context.project_name.clone(), |name| name.to_string_lossy().into_owned());
+    Ok(parent.join(format!(".{}.jumake-{}", name, std::process::id())))
+}
+
+/// Runs the creation steps in `context.project_path`, stopping at the first failure.
+fn create_project_files(context: &Context, commit_message: Option<&str>) -> Result<(), JuMakeError> {
+    // Step 1: Create project directory
+    fs::create_dir_all(&context.project_path)?;
+
+    // Step 2: Write jumake.toml
+    context.manifest.write(&context.project_path).map_err(failed(MANIFEST_FILE_NAME))?;
+
+    // Step 3: Create CMakeLists.txt
+    create_cmakelists(context).map_err(failed("CMakeLists.txt"))?;
+
+    // Step 4: Create CMakePresets.json
+    write_presets(context).map_err(failed(PRESETS_FILE))?;
+
+    // Step 5: Create source files
+    create_source_files(context).map_err(failed("source files"))?;
+
+    // Step 6: Initialize Git repository
+    initialize_git_repo(context).map_err(failed("Git repository"))?;
+
+    // Step 7: Create initial commit
+    if let Some(message) = commit_message {
+        create_initial_commit(context, message).map_err(failed("initial commit"))?;
+    }
+    Ok(())
+}
+
+/// Wraps the error of a creation step.
+fn failed<E: Into<Box<dyn Error + Send + Sync>>>(step: &'static str) -> impl FnOnce(E) -> JuMakeError {
+    move |e| JuMakeError::CreateStep { step, source: e.into() }
+}
@@ -0,0 +1,239 @@
+// src/diagnostics.rs
+//! Compiler diagnostics parsed from the output of `cmake --build`.
+//!
+//! GCC and Clang report problems as `file:line:column: severity: message`. Notes following an
+//! error or warning are attached to it; everything else (progress lines, `In file included from`,
+//! source excerpts) is ignored.
+
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the raw build output saved in `jumake_build/`.
+pub const BUILD_LOG_FILE: &str = "build.log";
+
+lazy_static! {
+    static ref DIAGNOSTIC_RE: Regex = Regex::new(
+        r"^((?:[A-Za-z]:)?[^:]+):(\d+):(?:(\d+):)?\s*(fatal error|error|warning|note):\s*(.*)$"
+    )
+    .unwrap();
+    static ref ANSI_ESCAPE_RE: Regex = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap();
+}
+
+/// How `jumake build` reports diagnostics, as selected with `--diagnostics`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DiagnosticsFormat {
+    /// A summary grouped by file after the build output
+    #[default]
+    Text,
+    /// A single JSON document on stdout; the build output goes to stderr
+    Json,
+    /// One `file:line:column: severity: message` line each on stdout, for editors' problem matchers;
+    /// the build output goes to stderr
+    Gcc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// A single error, warning or note.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+    /// Notes explaining an error or warning
+    pub notes: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    /// `line:column`, or just the line if the compiler gave no column.
+    fn position(&self) -> String {
+        match self.column {
+            Some(column) => format!("{}:{}", self.line, column),
+            None => self.line.to_string(),
+        }
+    }
+
+    /// The diagnostic and its notes in GCC's own format.
+    fn to_gcc(&self) -> Vec<String> {
+        let mut lines =
+            vec![format!("{}:{}: {}: {}", self.file, self.position(), self.severity.label(), self.message)];
+        lines.extend(self.notes.iter().flat_map(Diagnostic::to_gcc));
+        lines
+    }
+}
+
+/// The JSON form of [`Diagnostics`], with the counts and the build log in front.
+#[derive(Serialize)]
+struct DiagnosticsSummary<'a> {
+    errors: usize,
+    warnings: usize,
+    log: &'a Path,
+    diagnostics: &'a [Diagnostic],
+}
+
+/// The diagnostics of a build, in the order the compiler reported them.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Parses the output of a build. Relative file names are resolved against `build_dir`, the
+    /// directory the compiler ran in. Diagnostics reported more than once (e.g. for a header
+    /// included by several files) are kept once.
+    pub fn parse(output: &str, build_dir: &Path) -> Self {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        // Whether notes belong to the last diagnostic, rather than to a skipped duplicate
+        let mut attach_notes = false;
+        for line in output.lines() {
+            let line = ANSI_ESCAPE_RE.replace_all(line.trim_end(), "");
+            let Some(captures) = DIAGNOSTIC_RE.captures(&line) else {
+                continue;
+            };
+            let Ok(line_number) = captures[2].parse() else {
+                continue;
+            };
+            let severity = match &captures[4] {
+                "note" => Severity::Note,
+                "warning" => Severity::Warning,
+                _ => Severity::Error,
+            };
+            let diagnostic = Diagnostic {
+                file: normalize(&build_dir.join(&captures[1])).display().to_string(),
+                line: line_number,
+                column: captures.get(3).and_then(|column| column.as_str().parse().ok()),
+                severity,
+                message: captures[5].to_string(),
+                notes: Vec::new(),
+            };
+
+            if severity == Severity::Note {
+                if let Some(last) = diagnostics.last_mut().filter(|_| attach_notes) {
+                    last.notes.push(diagnostic);
+                }
+            } else {
+                attach_notes = !diagnostics.iter().any(|known| {
+                    known.file == diagnostic.file
+                        && known.line == diagnostic.line
+                        && known.column == diagnostic.column
+                        && known.severity == diagnostic.severity
+                        && known.message == diagnostic.message
+                });
+                if attach_notes {
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+        Self { diagnostics }
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == severity).count()
+    }
+
+    /// Prints the diagnostics grouped by file: files with the most errors first, and errors
+    /// before warnings within a file. File names are shown relative to `project_path`.
+    pub fn print_summary(&self, project_path: &Path) {
+        if self.diagnostics.is_empty() {
+            return;
+        }
+        let project_path = project_path.canonicalize().unwrap_or_else(|_| project_path.to_path_buf());
+        let display = |file: &str| {
+            Path::new(file)
+                .strip_prefix(&project_path)
+                .map_or_else(|_| file.to_string(), |relative| relative.display().to_string())
+        };
+
+        let mut files: BTreeMap<&str, Vec<&Diagnostic>> = BTreeMap::new();
+        for diagnostic in &self.diagnostics {
+            files.entry(&diagnostic.file).or_default().push(diagnostic);
+        }
+        let count = |diagnostics: &[&Diagnostic], severity| {
+            diagnostics.iter().filter(|diagnostic| diagnostic.severity == severity).count()
+        };
+        let mut files: Vec<(&str, Vec<&Diagnostic>)> = files.into_iter().collect();
+        files.sort_by_key(|(_, diagnostics)| {
+            std::cmp::Reverse((count(diagnostics, Severity::Error), count(diagnostics, Severity::Warning)))
+        });
+
+        println!(
+            "\nDiagnostics: {} error(s), {} warning(s)",
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        );
+        for (file, mut diagnostics) in files {
+            diagnostics.sort_by_key(|diagnostic| (diagnostic.severity, diagnostic.line, diagnostic.column));
+            println!(
+                "{}: {} error(s), {} warning(s)",
+                display(file),
+                count(&diagnostics, Severity::Error),
+                count(&diagnostics, Severity::Warning)
+            );
+            for diagnostic in diagnostics {
+                let icon = if diagnostic.severity == Severity::Error { "❌" } else { "⚠️ " };
+                println!("  {} {}: {}", icon, diagnostic.position(), diagnostic.message);
+                for note in &diagnostic.notes {
+                    let location = if note.file == diagnostic.file {
+                        note.position()
+                    } else {
+                        format!("{}:{}", display(&note.file), note.position())
+                    };
+                    println!("     note: {}: {}", location, note.message);
+                }
+            }
+        }
+    }
+
+    /// The diagnostics as a JSON document: `{"errors", "warnings", "log", "diagnostics"}`.
+    pub fn to_json(&self, log_path: &Path) -> String {
+        let summary = DiagnosticsSummary {
+            errors: self.count(Severity::Error),
+            warnings: self.count(Severity::Warning),
+            log: log_path,
+            diagnostics: &self.diagnostics,
+        };
+        serde_json::to_string(&summary).expect("Diagnostics always serialize")
+    }
+
+    /// The diagnostics in GCC's format, one line each, notes included.
+    pub fn to_gcc(&self) -> Vec<String> {
+        self.diagnostics.iter().flat_map(Diagnostic::to_gcc).collect()
+    }
+}
+
+/// Removes `.` and `..` components without resolving symbolic links, like the compiler does.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
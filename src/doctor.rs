@@ -0,0 +1,450 @@
+// src/doctor.rs
+//! Environment diagnostics for `jumake doctor`.
+//!
+//! Checks the tools a JuMake build needs (CMake, a C++ compiler, Ninja, ccache), the
+//! registered JUCE installs, JUCE's system dependencies on Linux and, inside a project, the
+//! state of `jumake_build/`. Every problem comes with a suggested fix.
+
+use crate::build::{configured_build_dirs, is_multi_config, read_cmake_cache, BUILD_DIR};
+use crate::create_files::CMAKE_MINIMUM_VERSION;
+use crate::config::load_config;
+use crate::initialize_git::JUCE_SUBMODULE_PATH;
+use crate::juce::read_juce_version;
+use crate::manifest::{JuceSource, ProjectManifest, MANIFEST_FILE_NAME};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use which::which;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    /// Builds work, but something is missing or suboptimal.
+    Warning,
+    /// Builds are expected to fail.
+    Error,
+}
+
+impl Status {
+    fn icon(self) -> &'static str {
+        match self {
+            Status::Ok => "✅",
+            Status::Warning => "⚠️ ",
+            Status::Error => "❌",
+        }
+    }
+}
+
+/// A single diagnostic with an optional suggested fix.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: Status::Ok, detail: detail.into(), fix: None }
+    }
+
+    fn warning(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: Status::Warning, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn error(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: Status::Error, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// The results of all checks.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+/// The JSON form of a [`Report`]: the checks with a summary in front.
+#[derive(Serialize)]
+struct ReportSummary<'a> {
+    ok: bool,
+    errors: usize,
+    warnings: usize,
+    #[serde(flatten)]
+    report: &'a Report,
+}
+
+impl Report {
+    /// Number of checks with the given status.
+    pub fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+
+    /// Prints the report for humans.
+    pub fn print(&self) {
+        for check in &self.checks {
+            println!("{} {}: {}", check.status.icon(), check.name, check.detail);
+            if let Some(fix) = &check.fix {
+                println!("   → {}", fix);
+            }
+        }
+        match (self.count(Status::Error), self.count(Status::Warning)) {
+            (0, 0) => println!("\nAll checks passed."),
+            (errors, warnings) => println!("\n{} error(s), {} warning(s).", errors, warnings),
+        }
+    }
+
+    /// The report as a JSON document.
+    pub fn to_json(&self) -> String {
+        let summary = ReportSummary {
+            ok: self.count(Status::Error) == 0,
+            errors: self.count(Status::Error),
+            warnings: self.count(Status::Warning),
+            report: self,
+        };
+        serde_json::to_string(&summary).expect("Reports always serialize")
+    }
+}
+
+/// Runs all checks; project checks are included if `project_path` holds a `jumake.toml`
+/// or `jumake_build/`.
+pub fn run_doctor(project_path: Option<&Path>) -> Report {
+    let project_path = project_path
+        .filter(|path| path.join(MANIFEST_FILE_NAME).is_file() || path.join("jumake_build").is_dir());
+    // Read only: doctor must not migrate a legacy project
+    let manifest = project_path.and_then(|path| ProjectManifest::read(path).ok());
+    let generator = manifest.as_ref().and_then(|manifest| manifest.build.generator.as_deref());
+
+    let mut checks = vec![check_cmake(), check_compiler(), check_ninja(generator), check_ccache()];
+    checks.extend(check_juce(project_path, manifest.as_ref()));
+    if cfg!(target_os = "linux") {
+        checks.extend(check_linux_dependencies());
+    }
+    if let Some(project_path) = project_path {
+        checks.push(check_build_dir(project_path, manifest.as_ref()));
+    }
+    Report { checks }
+}
+
+/// Whether a dotted version such as `3.28.3` is at least `minimum` such as `3.24`.
+pub fn version_at_least(version: &str, minimum: &str) -> bool {
+    let parse = |version: &str| -> Vec<u32> {
+        version
+            .split('.')
+            .map(|part| part.chars().take_while(char::is_ascii_digit).collect::<String>())
+            .map_while(|digits| digits.parse().ok())
+            .collect()
+    };
+    parse(version) >= parse(minimum)
+}
+
+/// First line of `<program> --version`, if the program is on the PATH.
+fn tool_version(program: &str) -> Option<String> {
+    let path = which(program).ok()?;
+    let output = Command::new(path).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
+
+/// Installation hint for the current platform.
+fn install_hint(apt: &str, brew: &str, windows: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!("Install it with `brew install {}`", brew)
+    } else if cfg!(target_os = "windows") {
+        windows.to_string()
+    } else {
+        format!("Install it with `sudo apt install {}` (or your distribution's equivalent)", apt)
+    }
+}
+
+fn check_cmake() -> Check {
+    let fix = format!(
+        "{} — JuMake projects need CMake {} or newer",
+        install_hint("cmake", "cmake", "Install CMake from https://cmake.org/download/"),
+        CMAKE_MINIMUM_VERSION
+    );
+    let Some(first_line) = tool_version("cmake") else {
+        return Check::error("cmake", "CMake not found on the PATH", fix);
+    };
+    // "cmake version 3.28.3"
+    let version = first_line.split_whitespace().last().unwrap_or_default();
+    if version_at_least(version, CMAKE_MINIMUM_VERSION) {
+        Check::ok("cmake", format!("CMake {}", version))
+    } else {
+        Check::error(
+            "cmake",
+            format!("CMake {} is older than the required {}", version, CMAKE_MINIMUM_VERSION),
+            fix,
+        )
+    }
+}
+
+fn check_compiler() -> Check {
+    let fix = if cfg!(target_os = "macos") {
+        "Install the Xcode command line tools with `xcode-select --install`".to_string()
+    } else if cfg!(target_os = "windows") {
+        "Install Visual Studio with the \"Desktop development with C++\" workload".to_string()
+    } else {
+        "Install a C++ compiler, e.g. `sudo apt install build-essential`".to_string()
+    };
+
+    // CMake prefers the compiler named by CXX
+    if let Ok(cxx) = std::env::var("CXX") {
+        let program = cxx.split_whitespace().next().unwrap_or_default();
+        return match tool_version(program) {
+            Some(version) => Check::ok("compiler", format!("{} (from CXX)", version)),
+            None => Check::error(
+                "compiler",
+                format!("CXX is set to '{}', which was not found", cxx),
+                "Point CXX at an installed C++ compiler or unset it",
+            ),
+        };
+    }
+
+    if cfg!(target_os = "windows") {
+        return match which("cl") {
+            Ok(path) => Check::ok("compiler", format!("MSVC at {}", path.display())),
+            Err(_) => Check::warning(
+                "compiler",
+                "cl.exe is not on the PATH (CMake can still find Visual Studio on its own)",
+                fix,
+            ),
+        };
+    }
+    match ["c++", "g++", "clang++"].iter().find_map(|program| tool_version(program)) {
+        Some(version) => Check::ok("compiler", version),
+        None => Check::error("compiler", "No C++ compiler found (tried c++, g++, clang++)", fix),
+    }
+}
+
+fn check_ninja(generator: Option<&str>) -> Check {
+    let uses_ninja = generator.is_some_and(|generator| generator.starts_with("Ninja"));
+    match tool_version("ninja") {
+        Some(version) => Check::ok("ninja", format!("Ninja {}", version)),
+        None if uses_ninja => Check::error(
+            "ninja",
+            format!(
+                "jumake.toml selects the '{}' generator, but Ninja was not found",
+                generator.unwrap_or_default()
+            ),
+            install_hint("ninja-build", "ninja", "Install Ninja from https://ninja-build.org"),
+        ),
+        None => Check::warning(
+            "ninja",
+            "Ninja not found — builds fall back to Unix Makefiles",
+            install_hint("ninja-build", "ninja", "Install Ninja from https://ninja-build.org"),
+        ),
+    }
+}
+
+fn check_ccache() -> Check {
+    match tool_version("ccache") {
+        Some(version) => Check::ok("ccache", version),
+        None => Check::warning(
+            "ccache",
+            "ccache not found — rebuilds won't use a compiler cache",
+            install_hint("ccache", "ccache", "Install ccache from https://ccache.dev"),
+        ),
+    }
+}
+
+/// Checks the registered JUCE installs and, inside a project, how it obtains JUCE.
+fn check_juce(project_path: Option<&Path>, manifest: Option<&ProjectManifest>) -> Vec<Check> {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![Check::error(
+                "juce",
+                format!("Cannot read the JuMake configuration: {}", e),
+                "Fix or delete the configuration file, then register JUCE with `jumake juce add <name> <path>`",
+            )]
+        }
+    };
+
+    let mut checks = Vec::new();
+    let default_name = config.default_install().map(|(name, _)| name.clone());
+    for (name, install) in &config.juce_installs {
+        let check_name = format!("juce install '{}'", name);
+        let is_default = default_name.as_deref() == Some(name.as_str());
+        let detail = if is_default { " (default)" } else { "" };
+        let fix = format!(
+            "Re-register it with `jumake juce remove {} && jumake juce add {} <path>`",
+            name, name
+        );
+        checks.push(if !install.path.is_dir() {
+            let detail = format!("{} does not exist{}", install.path.display(), detail);
+            if is_default {
+                Check::error(&check_name, detail, fix)
+            } else {
+                Check::warning(&check_name, detail, fix)
+            }
+        } else {
+            match read_juce_version(&install.path) {
+                Some(version) => {
+                    Check::ok(&check_name, format!("JUCE {} at {}{}", version, install.path.display(), detail))
+                }
+                None => Check::warning(
+                    &check_name,
+                    format!("{} is not a JUCE folder{}", install.path.display(), detail),
+                    fix,
+                ),
+            }
+        });
+    }
+    if config.juce_installs.is_empty() {
+        checks.push(Check::warning(
+            "juce",
+            "No JUCE install registered — `jumake new` will ask for one",
+            "Register your JUCE folder with `jumake juce add <name> <path>`",
+        ));
+    }
+
+    let (Some(project_path), Some(manifest)) = (project_path, manifest) else {
+        return checks;
+    };
+    let juce_dir = project_path.join(JUCE_SUBMODULE_PATH);
+    checks.push(match &manifest.juce {
+        JuceSource::Symlink { version: Some(pinned) } if config.find_install(pinned).is_none() => Check::error(
+            "project juce",
+            format!("The project pins JUCE {}, which is not registered", pinned),
+            "Register it with `jumake juce add <name> <path>` or pin another with `jumake juce use`",
+        ),
+        JuceSource::Symlink { .. } | JuceSource::Submodule { .. } if !juce_dir.join("CMakeLists.txt").is_file() => {
+            Check::warning(
+                "project juce",
+                format!("{} is missing or broken", JUCE_SUBMODULE_PATH),
+                "Run `jumake build`, which restores it",
+            )
+        }
+        JuceSource::Symlink { .. } | JuceSource::Submodule { .. } => Check::ok(
+            "project juce",
+            format!(
+                "JUCE {} in {}",
+                read_juce_version(&juce_dir).as_deref().unwrap_or("(unknown version)"),
+                JUCE_SUBMODULE_PATH
+            ),
+        ),
+        JuceSource::FetchContent { url, .. } => match std::env::var("FETCHCONTENT_SOURCE_DIR_JUCE") {
+            Ok(source_dir) => {
+                Check::ok("project juce", format!("FetchContent, using the local checkout {}", source_dir))
+            }
+            Err(_) => Check::ok("project juce", format!("FetchContent from {} at configure time", url)),
+        },
+    });
+    checks
+}
+
+/// System packages JUCE needs on Linux: display name, pkg-config names (any of them),
+/// Debian packages and, for optional ones, the JUCE option that needs them.
+///
+/// The templates build with `JUCE_WEB_BROWSER=0` and `JUCE_USE_CURL=0`.
+const LINUX_DEPENDENCIES: &[(&str, &[&str], &str, Option<&str>)] = &[
+    ("ALSA", &["alsa"], "libasound2-dev", None),
+    ("freetype", &["freetype2"], "libfreetype-dev", None),
+    (
+        "X11",
+        &["x11"],
+        "libx11-dev libxcomposite-dev libxcursor-dev libxext-dev libxinerama-dev libxrandr-dev libxrender-dev",
+        None,
+    ),
+    ("webkit2gtk", &["webkit2gtk-4.1", "webkit2gtk-4.0"], "libwebkit2gtk-4.1-dev", Some("JUCE_WEB_BROWSER")),
+    ("curl", &["libcurl"], "libcurl4-openssl-dev", Some("JUCE_USE_CURL")),
+];
+
+fn check_linux_dependencies() -> Vec<Check> {
+    if which("pkg-config").is_err() {
+        return vec![Check::warning(
+            "pkg-config",
+            "pkg-config not found — cannot check JUCE's system dependencies",
+            "Install it with `sudo apt install pkg-config`",
+        )];
+    }
+
+    LINUX_DEPENDENCIES
+        .iter()
+        .map(|(name, packages, apt, needed_by)| {
+            let found = packages.iter().find_map(|package| pkg_config_version(package));
+            let fix = format!("Install it with `sudo apt install {}` (or your distribution's equivalent)", apt);
+            match (found, needed_by) {
+                (Some(version), _) => Check::ok(name, format!("{} {}", name, version)),
+                (None, None) => Check::error(name, format!("{} development files not found", name), fix),
+                (None, Some(option)) => Check::warning(
+                    name,
+                    format!("{} development files not found — only needed with {}=1", name, option),
+                    fix,
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Version of an installed pkg-config package.
+fn pkg_config_version(package: &str) -> Option<String> {
+    let output = Command::new("pkg-config").arg("--modversion").arg(package).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Checks that the build directories in `jumake_build/` were configured for this project and
+/// its current settings.
+pub fn check_build_dir(project_path: &Path, manifest: Option<&ProjectManifest>) -> Check {
+    if !project_path.join(BUILD_DIR).is_dir() {
+        return Check::ok("build directory", "Not configured yet — `jumake build` will configure it");
+    }
+    let build_dirs = configured_build_dirs(project_path);
+    if build_dirs.is_empty() {
+        return Check::warning(
+            "build directory",
+            "jumake_build/ has no CMakeCache.txt — the last configure probably failed",
+            "Run `jumake build` to configure again",
+        );
+    }
+
+    let mut configured = Vec::new();
+    for build_dir in build_dirs {
+        let name = build_dir.strip_prefix(project_path).unwrap_or(&build_dir).display().to_string();
+        let cache = read_cmake_cache(&build_dir.join("CMakeCache.txt")).unwrap_or_default();
+
+        // A copied or moved project keeps a cache pointing at the old location
+        if let Some(home) = cache.get("CMAKE_HOME_DIRECTORY") {
+            let same_project = match (Path::new(home).canonicalize(), project_path.canonicalize()) {
+                (Ok(home), Ok(project)) => home == project,
+                _ => false,
+            };
+            if !same_project {
+                return Check::error(
+                    "build directory",
+                    format!("{}/ was configured for {} — the project was moved or copied", name, home),
+                    "Run `jumake build` to configure it from scratch",
+                );
+            }
+        }
+
+        let generator = cache.get("CMAKE_GENERATOR").map(String::as_str).unwrap_or("unknown generator");
+        if let Some(wanted) = manifest.and_then(|manifest| manifest.build.generator.as_deref()) {
+            if wanted != generator {
+                return Check::warning(
+                    "build directory",
+                    format!(
+                        "{}/ was configured with '{}', but jumake.toml selects '{}'",
+                        name,
+                        generator,
+                        wanted
+                    ),
+                    "Run `jumake build` to configure it from scratch",
+                );
+            }
+        }
+        let build_type = match cache.get("CMAKE_BUILD_TYPE").map(String::as_str) {
+            _ if is_multi_config(generator) => "all build types",
+            Some(build_type) if !build_type.is_empty() => build_type,
+            _ => "no build type",
+        };
+        configured.push(format!("{} ({})", generator, build_type));
+    }
+    Check::ok("build directory", format!("Configured with {}", configured.join(", ")))
+}
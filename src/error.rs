@@ -0,0 +1,241 @@
+// src/error.rs
+//! The errors of JuMake and the error type returned by every command of the CLI.
+//!
+//! Module errors ([`JuMakeError`], [`BuildError`] and `anyhow` errors with their context) are
+//! wrapped in a [`CliError`] that records which [`ErrorKind`] of failure occurred. The kind
+//! decides the process exit code, so scripts can tell a broken configuration from a failed
+//! compile. Invalid input is reported as [`JuMakeError::Invalid`], also from inside `anyhow`
+//! chains.
+
+use crate::build::BuildError;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Errors of project creation, the global configuration, Git and invalid input.
+#[derive(Debug, Error)]
+pub enum JuMakeError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Git error: {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("TOML parse error: {0}")]
+    TomlDe(#[from] toml::de::Error),
+
+    #[error("TOML serialization error: {0}")]
+    TomlSer(#[from] toml::ser::Error),
+
+    #[error("Dialoguer error: {0}")]
+    Dialoguer(#[from] dialoguer::Error),
+
+    /// An invalid name, value or argument given by the user, e.g. a class name that isn't a
+    /// C++ identifier or a module that doesn't exist.
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Project directory already exists: {}", .0.display())]
+    ProjectExists(PathBuf),
+
+    #[error("Failed to create {step}: {source}")]
+    CreateStep {
+        step: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("Symlink error from {src} to {dst}: {source}")]
+    Symlink {
+        src: String,
+        dst: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Category of a failure, each with its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    /// Anything not covered below, e.g. I/O errors or invalid project files (exit code 1)
+    Other,
+    /// Invalid arguments or missing input (exit code 2, like clap's own usage errors)
+    Usage,
+    /// Global configuration, JUCE installs or `jumake.toml` (exit code 3)
+    Config,
+    /// Git repository or submodule operations (exit code 4)
+    Git,
+    /// The CMake configure step (exit code 5)
+    Configure,
+    /// The CMake build step (exit code 6)
+    Compile,
+    /// Running the built executable (exit code 7)
+    Run,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Config => 3,
+            ErrorKind::Git => 4,
+            ErrorKind::Configure => 5,
+            ErrorKind::Compile => 6,
+            ErrorKind::Run => 7,
+        }
+    }
+}
+
+/// How errors are printed, as selected with `--error-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// `❌ Error: ...` followed by the causes
+    #[default]
+    Text,
+    /// A single JSON object on stderr
+    Json,
+}
+
+/// The JSON form of a [`CliError`], as the `error` member of the document.
+#[derive(Serialize)]
+struct ErrorPayload<'a> {
+    kind: ErrorKind,
+    exit_code: i32,
+    message: &'a str,
+    causes: &'a [String],
+}
+
+/// A failed command: the error with its chain of causes and its category.
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    error: anyhow::Error,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, error: impl Into<anyhow::Error>) -> Self {
+        Self { kind, error: error.into() }
+    }
+
+    /// An invalid argument or missing input.
+    pub fn usage(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Usage, anyhow::anyhow!("{}", message))
+    }
+
+    /// The error message followed by its causes.
+    ///
+    /// Causes already spelled out by the message before them (as with `#[error("...: {0}")]`)
+    /// are skipped.
+    pub fn messages(&self) -> Vec<String> {
+        let mut messages: Vec<String> = Vec::new();
+        for cause in self.error.chain() {
+            let message = cause.to_string();
+            if !messages.last().is_some_and(|previous| previous.contains(&message)) {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    /// The error as a JSON document: `{"error": {"kind", "exit_code", "message", "causes"}}`.
+    pub fn to_json(&self) -> String {
+        let messages = self.messages();
+        let error = ErrorPayload {
+            kind: self.kind,
+            exit_code: self.kind.exit_code(),
+            message: &messages[0],
+            causes: &messages[1..],
+        };
+        serde_json::json!({ "error": error }).to_string()
+    }
+
+    /// Prints the error to stderr in the given format.
+    pub fn report(&self, format: ErrorFormat) {
+        match format {
+            ErrorFormat::Text => {
+                let messages = self.messages();
+                eprintln!("❌ Error: {}", messages[0]);
+                for cause in &messages[1..] {
+                    eprintln!("   caused by: {}", cause);
+                }
+            }
+            ErrorFormat::Json => eprintln!("{}", self.to_json()),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl From<JuMakeError> for CliError {
+    fn from(error: JuMakeError) -> Self {
+        Self::new(jumake_error_kind(&error), error)
+    }
+}
+
+impl From<BuildError> for CliError {
+    fn from(error: BuildError) -> Self {
+        Self::new(build_error_kind(&error), error)
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(error: std::io::Error) -> Self {
+        Self::new(ErrorKind::Other, error)
+    }
+}
+
+impl From<anyhow::Error> for CliError {
+    /// Categorizes the error by the first known error type in its chain.
+    fn from(error: anyhow::Error) -> Self {
+        let kind = error
+            .chain()
+            .find_map(|cause| {
+                if let Some(error) = cause.downcast_ref::<JuMakeError>() {
+                    Some(jumake_error_kind(error))
+                } else if let Some(error) = cause.downcast_ref::<BuildError>() {
+                    Some(build_error_kind(error))
+                } else if cause.is::<git2::Error>() {
+                    Some(ErrorKind::Git)
+                } else if cause.is::<toml::de::Error>() {
+                    Some(ErrorKind::Config)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorKind::Other);
+        Self { kind, error }
+    }
+}
+
+fn jumake_error_kind(error: &JuMakeError) -> ErrorKind {
+    match error {
+        JuMakeError::Io(_) | JuMakeError::Symlink { .. } => ErrorKind::Other,
+        JuMakeError::Git(_) => ErrorKind::Git,
+        JuMakeError::Config(_) | JuMakeError::TomlDe(_) | JuMakeError::TomlSer(_) => ErrorKind::Config,
+        JuMakeError::Dialoguer(_) | JuMakeError::Invalid(_) | JuMakeError::ProjectExists(_) => ErrorKind::Usage,
+        JuMakeError::CreateStep { step: "Git repository" | "initial commit", .. } => ErrorKind::Git,
+        JuMakeError::CreateStep { .. } => ErrorKind::Other,
+    }
+}
+
+fn build_error_kind(error: &BuildError) -> ErrorKind {
+    match error {
+        BuildError::Io(_) | BuildError::Utf8(_) => ErrorKind::Other,
+        BuildError::CMakeNotFound | BuildError::CMakeConfigureFailed | BuildError::CompileCommandsMissing => {
+            ErrorKind::Configure
+        }
+        BuildError::CMakeBuildFailed => ErrorKind::Compile,
+        BuildError::ExecutableNotFound(_) | BuildError::RunFailed(_) => ErrorKind::Run,
+    }
+}
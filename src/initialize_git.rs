@@ -2,105 +2,153 @@
 //! Module responsible for initializing a Git repository for a JuMake project.
 //! Handles `.gitignore`, adding all files, JUCE submodule linking, and initial commit.
 
+use crate::config::{config_file_path, install_name, load_config, save_config};
 use crate::context::Context;
+use crate::error::JuMakeError;
 use dialoguer::Input;
-use dirs;
-use git2::{Error as GitError, IndexAddOption, Repository, Signature};
+use crate::juce::read_juce_version;
+use crate::manifest::JuceSource;
+use git2::build::CheckoutBuilder;
+use git2::{Error as GitError, IndexAddOption, Object, Repository, Signature};
 use log::{info, warn};
-use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
-use thiserror::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(windows)]
 use std::os::windows::fs as windows_fs;
 
 // ------------------------
-// Custom error type
+// JUCE discovery and prompts
 // ------------------------
-#[derive(Debug, Error)]
-pub enum JuMakeError {
-    #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
 
-    #[error("Git error: {0}")]
-    Git(#[from] git2::Error),
+/// Environment variable pointing at a JUCE folder; overrides the registered installs.
+pub const JUCE_PATH_ENV: &str = "JUMAKE_JUCE_PATH";
 
-    #[error("Configuration error: {0}")]
-    Config(String),
+/// Places searched for a JUCE folder before asking for one (`~` is the home directory).
+const COMMON_JUCE_LOCATIONS: &[&str] = &["~/JUCE", "/opt/JUCE", "/usr/share/juce"];
 
-    #[error("TOML parse error: {0}")]
-    TomlDe(#[from] toml::de::Error),
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
 
-    #[error("TOML serialization error: {0}")]
-    TomlSer(#[from] toml::ser::Error),
+/// Disables all prompts, as requested by `--non-interactive`/`--yes`.
+pub fn set_non_interactive(non_interactive: bool) {
+    NON_INTERACTIVE.store(non_interactive, Ordering::Relaxed);
+}
 
-    #[error("Dialoguer error: {0}")]
-    Dialoguer(#[from] dialoguer::Error),
+/// Whether JuMake may prompt: not disabled on the command line and stdin is a terminal.
+pub fn is_interactive() -> bool {
+    !NON_INTERACTIVE.load(Ordering::Relaxed) && std::io::stdin().is_terminal()
+}
 
-    #[error("Symlink error from {src} to {dst}: {source}")]
-    Symlink {
-        src: String,
-        dst: String,
-        #[source]
-        source: std::io::Error,
-    },
+/// The JUCE folder from `JUMAKE_JUCE_PATH`, if set.
+fn juce_path_from_env() -> Result<Option<PathBuf>, JuMakeError> {
+    let Some(value) = std::env::var_os(JUCE_PATH_ENV).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+    let path = PathBuf::from(value);
+    if !path.is_dir() {
+        return Err(JuMakeError::Config(format!(
+            "{} is set to {}, which is not a directory",
+            JUCE_PATH_ENV,
+            path.display()
+        )));
+    }
+    if read_juce_version(&path).is_none() {
+        return Err(JuMakeError::Config(format!(
+            "{} is set to {}, which is not a JUCE folder: no 'project(JUCE VERSION ...)' in its CMakeLists.txt",
+            JUCE_PATH_ENV,
+            path.display()
+        )));
+    }
+    info!("Using JUCE from {}: {}", JUCE_PATH_ENV, path.display());
+    Ok(Some(path))
 }
 
-// ------------------------
-// Configuration handling
-// ------------------------
-#[derive(Serialize, Deserialize, Default)]
-struct JuMakeConfig {
-    juce_path: Option<PathBuf>,
+/// The first of [`COMMON_JUCE_LOCATIONS`] that holds a JUCE folder.
+fn find_common_juce_location() -> Option<PathBuf> {
+    COMMON_JUCE_LOCATIONS
+        .iter()
+        .filter_map(|location| match location.strip_prefix("~/") {
+            Some(relative) => dirs::home_dir().map(|home| home.join(relative)),
+            None => Some(PathBuf::from(location)),
+        })
+        .find(|path| read_juce_version(path).is_some())
 }
 
-/// Retrieves JUCE path from cached configuration or prompts the user.
-/// Writes the path atomically to prevent config corruption.
+/// Retrieves the path of the JUCE folder to use.
+///
+/// In order: `JUMAKE_JUCE_PATH`, the default install, a JUCE folder in one of the common
+/// locations (registered as an install) and finally a prompt, unless prompts are disabled.
 pub fn get_juce_path() -> Result<PathBuf, JuMakeError> {
-    // Platform-specific cache directory
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| JuMakeError::Config("Cannot determine cache directory".into()))?
-        .join("jumake");
+    if let Some(path) = juce_path_from_env()? {
+        return Ok(path);
+    }
+    let mut config = load_config()?;
 
-    fs::create_dir_all(&cache_dir)?;
-    let config_file = cache_dir.join("config.toml");
+    if let Some((name, install)) = config.default_install() {
+        info!("Using JUCE install '{}' at {}", name, install.path.display());
+        return Ok(install.path.clone());
+    }
 
-    // Load or initialize config
-    let mut config: JuMakeConfig = if config_file.exists() {
-        toml::from_str(&fs::read_to_string(&config_file)?)?
-    } else {
-        JuMakeConfig::default()
-    };
+    if let Some(path) = find_common_juce_location() {
+        let name = install_name(read_juce_version(&path).as_deref());
+        config.add_install(&name, path.clone());
+        save_config(&config)?;
+        println!("Found JUCE at {} and registered it as install '{}'.", path.display(), name);
+        return Ok(path);
+    }
 
-    // Prompt user if JUCE path is missing
-    if config.juce_path.is_none() {
-        let input_path: String = Input::new()
-            .with_prompt("Enter path to your local JUCE folder")
-            .validate_with(|input: &String| {
-                let p = Path::new(input);
-                if p.exists() && p.is_dir() {
-                    Ok(())
-                } else {
-                    Err("Path does not exist or is not a directory")
-                }
-            })
-            .interact_text()?;
+    if !is_interactive() {
+        return Err(JuMakeError::Config(format!(
+            "No JUCE install found. Set {} or register one with `jumake juce add <name> <path>`",
+            JUCE_PATH_ENV
+        )));
+    }
 
-        let pathbuf = PathBuf::from(&input_path);
+    // Prompt user if no JUCE install is registered
+    let input_path: String = Input::new()
+        .with_prompt("Enter path to your local JUCE folder")
+        .validate_with(|input: &String| {
+            let p = Path::new(input);
+            if p.exists() && p.is_dir() {
+                Ok(())
+            } else {
+                Err("Path does not exist or is not a directory")
+            }
+        })
+        .interact_text()?;
 
-        // Atomic write to avoid corrupt config
-        let tmp_file = config_file.with_extension("tmp");
-        fs::write(&tmp_file, toml::to_string(&JuMakeConfig { juce_path: Some(pathbuf.clone()) })?)?;
-        fs::rename(&tmp_file, &config_file)?;
+    let path = PathBuf::from(&input_path);
+    let name = install_name(read_juce_version(&path).as_deref());
+    config.add_install(&name, path.clone());
+    config.default_juce = Some(name.clone());
+    save_config(&config)?;
+    info!("✅ JUCE install '{}' saved to {}", name, config_file_path()?.display());
 
-        config.juce_path = Some(pathbuf);
-        info!("✅ JUCE path saved to {}", config_file.display());
-    } else {
-        info!("Using cached JUCE path from {}", config_file.display());
-    }
+    Ok(path)
+}
 
-    Ok(config.juce_path.expect("JUCE path must be set"))
+/// Retrieves the JUCE install pinned by a project (by version or install name), falling back
+/// to the default install with a warning if it is not registered. `JUMAKE_JUCE_PATH` takes
+/// precedence over both.
+pub fn resolve_juce_path(pinned: Option<&str>) -> Result<PathBuf, JuMakeError> {
+    if let Some(path) = juce_path_from_env()? {
+        return Ok(path);
+    }
+    if let Some(pinned) = pinned {
+        let config = load_config()?;
+        if let Some((name, install)) = config.find_install(pinned) {
+            info!("Using JUCE install '{}' at {}", name, install.path.display());
+            return Ok(install.path.clone());
+        }
+        eprintln!(
+            "⚠️  JUCE {} pinned by this project is not installed — using the default JUCE install. \
+             Register it with `jumake juce add <name> <path>`.",
+            pinned
+        );
+    }
+    get_juce_path()
 }
 
 // ------------------------
@@ -112,9 +160,9 @@ pub fn initialize_git_repo(context: &Context) -> Result<(), JuMakeError> {
     let repo = Repository::init(&context.project_path)?;
     info!("Git repository initialized successfully.");
 
-    append_gitignore(&context.project_path)?;
+    append_gitignore(context)?;
     add_all_files_to_repo(&repo)?;
-    add_juce_submodule(context)?;
+    add_juce(context, Some(&repo))?;
 
     stage_gitmodules_if_exists(&repo, &context.project_path)?;
 
@@ -144,7 +192,7 @@ fn stage_gitmodules_if_exists(repo: &Repository, project_path: &Path) -> Result<
 // .gitignore handling
 // ------------------------
 const DEFAULT_GITIGNORE: &[&str] = &[
-    "modules/",
+    "modules/JUCE",
     "jumake_build/",
     "build/",
     "compile_commands.json",
@@ -152,12 +200,15 @@ const DEFAULT_GITIGNORE: &[&str] = &[
     ".cache/",
 ];
 
-fn append_gitignore(project_path: &Path) -> Result<(), JuMakeError> {
-    let gitignore_path = project_path.join(".gitignore");
+pub(crate) fn append_gitignore(context: &Context) -> Result<(), JuMakeError> {
+    let gitignore_path = context.project_path.join(".gitignore");
     let existing = fs::read_to_string(&gitignore_path).unwrap_or_default();
 
+    // A JUCE submodule lives in modules/ and must be committed
+    let is_submodule = matches!(context.manifest.juce, JuceSource::Submodule { .. });
     let new_entries: String = DEFAULT_GITIGNORE
         .iter()
+        .filter(|entry| !(is_submodule && **entry == "modules/JUCE"))
         .filter(|entry| !existing.contains(*entry))
         .map(|entry| format!("{}\n", entry))
         .collect();
@@ -174,11 +225,179 @@ fn append_gitignore(project_path: &Path) -> Result<(), JuMakeError> {
     Ok(())
 }
 
+/// Makes git ignore the custom module `modules/<name>` if `ignored` is set, as is done for
+/// symlinks into this machine, or track it otherwise, as a copied module must be committed.
+///
+/// Projects created by older versions ignore all of `modules/`; that entry is narrowed to
+/// `modules/JUCE` so a copied module isn't missing from clones.
+pub(crate) fn gitignore_module(context: &Context, name: &str, ignored: bool) -> Result<(), JuMakeError> {
+    let gitignore_path = context.project_path.join(".gitignore");
+    let existing = fs::read_to_string(&gitignore_path).unwrap_or_default();
+    let entry = format!("modules/{}", name);
+    let is_submodule = matches!(context.manifest.juce, JuceSource::Submodule { .. });
+
+    let mut lines: Vec<String> = existing.lines().map(String::from).collect();
+    if ignored {
+        if !lines.contains(&entry) {
+            lines.push(entry);
+        }
+    } else {
+        lines.retain(|line| line.trim_end_matches('/') != entry);
+        let all_modules = |line: &String| matches!(line.as_str(), "modules/" | "modules" | "/modules/" | "/modules");
+        if lines.iter().any(all_modules) {
+            lines.retain(|line| !all_modules(line));
+            if !is_submodule && !lines.iter().any(|line| line == "modules/JUCE") {
+                lines.push(String::from("modules/JUCE"));
+            }
+            println!("Updated .gitignore to ignore only modules/JUCE, so {} gets committed.", entry);
+        }
+    }
+
+    let content: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+    if content != existing {
+        let tmp_path = gitignore_path.with_extension("tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &gitignore_path)?;
+        info!("✅ Updated .gitignore at {}", gitignore_path.display());
+    }
+    Ok(())
+}
+
 // ------------------------
-// Add JUCE submodule (cross-platform symlink)
+// Add JUCE to modules/JUCE (git submodule or cross-platform symlink)
 // ------------------------
-fn add_juce_submodule(context: &Context) -> Result<(), JuMakeError> {
-    let juce_path = get_juce_path()?;
+/// Location of JUCE inside a project.
+pub const JUCE_SUBMODULE_PATH: &str = "modules/JUCE";
+
+/// Puts JUCE into `modules/JUCE` as configured in the project's `jumake.toml`.
+///
+/// In `FetchContent` mode CMake downloads JUCE at configure time, so nothing is done here.
+pub fn add_juce_submodule(context: &Context) -> Result<(), JuMakeError> {
+    add_juce(context, None)
+}
+
+/// Like [`add_juce_submodule`], reusing `repo` if given so its cached index stays current.
+fn add_juce(context: &Context, repo: Option<&Repository>) -> Result<(), JuMakeError> {
+    match &context.manifest.juce {
+        JuceSource::Symlink { .. } => link_local_juce(context),
+        JuceSource::Submodule { url, rev } => match repo {
+            Some(repo) => clone_juce_submodule(repo, url, rev.as_deref()),
+            None => clone_juce_submodule(&Repository::open(&context.project_path)?, url, rev.as_deref()),
+        },
+        JuceSource::FetchContent { .. } => Ok(()),
+    }
+}
+
+/// Deregisters the JUCE submodule and deletes its checkout, staging the changes.
+pub fn remove_juce_submodule(project_path: &Path) -> Result<(), JuMakeError> {
+    let repo = Repository::open(project_path)?;
+    let name = repo
+        .find_submodule(JUCE_SUBMODULE_PATH)?
+        .name()
+        .unwrap_or(JUCE_SUBMODULE_PATH)
+        .to_string();
+
+    let mut index = repo.index()?;
+    index.remove_path(Path::new(JUCE_SUBMODULE_PATH))?;
+
+    let gitmodules_path = project_path.join(".gitmodules");
+    let mut gitmodules = git2::Config::open(&gitmodules_path)?;
+    for key in ["path", "url", "branch"] {
+        // Missing keys are fine
+        let _ = gitmodules.remove(&format!("submodule.{}.{}", name, key));
+    }
+    drop(gitmodules);
+    let remaining = fs::read_to_string(&gitmodules_path)?;
+    if remaining.lines().any(|line| line.contains('=')) {
+        index.add_path(Path::new(".gitmodules"))?;
+    } else {
+        fs::remove_file(&gitmodules_path)?;
+        let _ = index.remove_path(Path::new(".gitmodules"));
+    }
+    index.write()?;
+
+    if let Ok(mut config) = repo.config() {
+        let _ = config.remove(&format!("submodule.{}.url", name));
+    }
+    let checkout = project_path.join(JUCE_SUBMODULE_PATH);
+    if checkout.exists() {
+        fs::remove_dir_all(&checkout)?;
+    }
+    // Drop the submodule's git directory so it can be added again later
+    let git_dir = repo.path().join("modules").join(&name);
+    if git_dir.exists() {
+        fs::remove_dir_all(&git_dir)?;
+    }
+
+    info!("✅ Removed JUCE submodule {}", name);
+    Ok(())
+}
+
+/// Restores `modules/JUCE` after a fresh clone, as recorded in the project's `jumake.toml`.
+///
+/// Does nothing if JUCE is already in place.
+pub fn restore_juce(context: &Context) -> Result<(), JuMakeError> {
+    let juce_dir = context.project_path.join(JUCE_SUBMODULE_PATH);
+    if juce_dir.join("CMakeLists.txt").is_file() {
+        return Ok(());
+    }
+
+    match &context.manifest.juce {
+        JuceSource::Symlink { .. } => link_local_juce(context)?,
+        JuceSource::Submodule { .. } => {
+            let repo = Repository::open(&context.project_path)?;
+            let mut submodule = repo.find_submodule(JUCE_SUBMODULE_PATH)?;
+            info!("Cloning JUCE submodule from {}", submodule.url().unwrap_or("<unknown>"));
+            submodule.update(true, None)?;
+        }
+        // CMake downloads JUCE at configure time
+        JuceSource::FetchContent { .. } => return Ok(()),
+    }
+    println!("Restored JUCE at {}", juce_dir.display());
+    Ok(())
+}
+
+/// Registers `url` as a git submodule at `modules/JUCE`, checked out at `rev` if given.
+fn clone_juce_submodule(repo: &Repository, url: &str, rev: Option<&str>) -> Result<(), JuMakeError> {
+    info!("Adding JUCE submodule from {}", url);
+    let mut submodule = repo.submodule(url, Path::new(JUCE_SUBMODULE_PATH), true)?;
+    let juce_repo = submodule.clone(None)?;
+
+    if let Some(rev) = rev {
+        let object = match resolve_revision(&juce_repo, rev) {
+            Some(object) => object,
+            None => {
+                // Tags not reachable from the default branch are not fetched by the clone
+                juce_repo.find_remote("origin")?.fetch(&["+refs/tags/*:refs/tags/*"], None, None)?;
+                resolve_revision(&juce_repo, rev).ok_or_else(|| {
+                    JuMakeError::Config(format!("JUCE revision '{}' not found in {}", rev, url))
+                })?
+            }
+        };
+        juce_repo.checkout_tree(&object, Some(CheckoutBuilder::new().force()))?;
+        juce_repo.set_head_detached(object.peel_to_commit()?.id())?;
+        info!("Checked out JUCE {}", rev);
+    }
+
+    submodule.add_finalize()?;
+    info!("✅ Added JUCE submodule at {}", JUCE_SUBMODULE_PATH);
+    Ok(())
+}
+
+/// Resolves a tag, branch or commit in a freshly cloned repository.
+fn resolve_revision<'r>(repo: &'r Repository, rev: &str) -> Option<Object<'r>> {
+    [rev.to_string(), format!("refs/tags/{}", rev), format!("origin/{}", rev)]
+        .iter()
+        .find_map(|spec| repo.revparse_single(spec).ok())
+}
+
+/// Symlinks `modules/JUCE` to the JUCE install pinned by the project, or the default one.
+fn link_local_juce(context: &Context) -> Result<(), JuMakeError> {
+    let pinned = match &context.manifest.juce {
+        JuceSource::Symlink { version } => version.as_deref(),
+        _ => None,
+    };
+    let juce_path = resolve_juce_path(pinned)?;
     if !juce_path.is_dir() {
         return Err(JuMakeError::Config(format!(
             "Local JUCE folder does not exist: {}",
@@ -219,7 +438,7 @@ fn add_juce_submodule(context: &Context) -> Result<(), JuMakeError> {
 // Cross-platform symlink creation
 // ------------------------
 #[cfg(unix)]
-fn create_symlink(src: &Path, dst: &Path) -> Result<(), JuMakeError> {
+pub(crate) fn create_symlink(src: &Path, dst: &Path) -> Result<(), JuMakeError> {
     std::os::unix::fs::symlink(src, dst).map_err(|source| JuMakeError::Symlink {
         src: src.display().to_string(),
         dst: dst.display().to_string(),
@@ -228,7 +447,7 @@ fn create_symlink(src: &Path, dst: &Path) -> Result<(), JuMakeError> {
 }
 
 #[cfg(windows)]
-fn create_symlink(src: &Path, dst: &Path) -> Result<(), JuMakeError> {
+pub(crate) fn create_symlink(src: &Path, dst: &Path) -> Result<(), JuMakeError> {
     windows_fs::symlink_dir(src, dst).map_err(|source| JuMakeError::Symlink {
         src: src.display().to_string(),
         dst: dst.display().to_string(),
@@ -249,9 +468,44 @@ fn add_all_files_to_repo(repo: &Repository) -> Result<(), GitError> {
 // ------------------------
 // Initial commit
 // ------------------------
-pub fn create_initial_commit(context: &Context) -> Result<(), JuMakeError> {
+/// Message of the initial commit unless `--commit-message` is given.
+pub const DEFAULT_COMMIT_MESSAGE: &str = "Initial commit by JuMake";
+
+/// Name and e-mail address of the project author: `user.name`/`user.email` from the Git
+/// configuration (of `repo` if given, otherwise the global one), falling back to
+/// `author_name`/`author_email` from the JuMake configuration.
+pub fn author_identity(repo: Option<&Repository>) -> (Option<String>, Option<String>) {
+    let git_config = match repo {
+        Some(repo) => repo.config(),
+        None => git2::Config::open_default(),
+    };
+    let git_value = |key: &str| {
+        git_config
+            .as_ref()
+            .ok()
+            .and_then(|config| config.get_string(key).ok())
+            .filter(|value| !value.trim().is_empty())
+    };
+    let config = load_config().unwrap_or_default();
+    (
+        git_value("user.name").or(config.author_name),
+        git_value("user.email").or(config.author_email),
+    )
+}
+
+/// Commits the staged project files with `message`.
+///
+/// Without an author identity the commit is skipped with a warning, as with `--no-commit`.
+pub fn create_initial_commit(context: &Context, message: &str) -> Result<(), JuMakeError> {
     let repo = Repository::open(&context.project_path)?;
-    let signature = Signature::now("JuMake", "jumake@example.com")?;
+    let (Some(name), Some(email)) = author_identity(Some(&repo)) else {
+        eprintln!(
+            "⚠️  No author for the initial commit, so the files are staged but not committed. Set \
+             `git config --global user.name/user.email` or `jumake config set author_name/author_email`."
+        );
+        return Ok(());
+    };
+    let signature = Signature::now(&name, &email)?;
 
     let tree_id = repo.index()?.write_tree()?;
     let tree = repo.find_tree(tree_id)?;
@@ -260,11 +514,11 @@ pub fn create_initial_commit(context: &Context) -> Result<(), JuMakeError> {
         Some("HEAD"),
         &signature,
         &signature,
-        "Initial commit by JuMake",
+        message,
         &tree,
         &[],
     )?;
 
-    info!("✅ Initial commit created (id: {})", commit_id);
+    info!("✅ Initial commit created as {} <{}> (id: {})", name, email, commit_id);
     Ok(())
 }
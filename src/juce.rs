@@ -0,0 +1,266 @@
+// src/juce.rs
+//! How a project obtains JUCE.
+//!
+//! The `[juce]` table of `jumake.toml` selects one of three modes: a symlink to a local JUCE
+//! folder, a git submodule, or CMake's `FetchContent`. This module builds the matching lines
+//! of the root `CMakeLists.txt` and switches existing projects between modes
+//! (`jumake juce mode`).
+//!
+//! It also manages the JUCE installs registered in the global configuration
+//! (`jumake juce list/add/remove/default`) and the install a symlink-mode project pins
+//! (`jumake juce use`).
+
+use crate::cmake_edit::{CMakeDocument, CommandId};
+use crate::context::Context;
+use crate::config::{load_config, save_config};
+use crate::initialize_git::{add_juce_submodule, append_gitignore, remove_juce_submodule, JUCE_SUBMODULE_PATH};
+use crate::juce_modules::remove_symlink;
+use crate::manifest::JuceSource;
+use anyhow::{Context as AnyhowContext, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+
+/// Repository used when a mode needs a JUCE URL and none was given.
+pub const DEFAULT_JUCE_REPOSITORY: &str = "https://github.com/juce-framework/JUCE.git";
+/// Tag used when a mode needs a pinned JUCE revision and none was given.
+pub const DEFAULT_JUCE_TAG: &str = "8.0.4";
+
+/// Comment explaining how to build a `FetchContent` project offline.
+const FETCHCONTENT_HINT: &str = "# set FETCHCONTENT_SOURCE_DIR_JUCE to build against a local JUCE checkout";
+
+/// The ways of obtaining JUCE, as selected on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum JuceMode {
+    /// Symlink `modules/JUCE` to the configured local JUCE folder
+    Symlink,
+    /// Add JUCE as a git submodule at `modules/JUCE`
+    Submodule,
+    /// Download JUCE at configure time with CMake's FetchContent
+    #[value(name = "fetchcontent")]
+    FetchContent,
+}
+
+impl JuceMode {
+    fn name(self) -> &'static str {
+        match self {
+            JuceMode::Symlink => "symlink",
+            JuceMode::Submodule => "submodule",
+            JuceMode::FetchContent => "fetchcontent",
+        }
+    }
+}
+
+/// Combines `--juce-mode` and a JUCE source spec (see [`JuceSource`]'s `FromStr`) into a source.
+///
+/// Without a mode the spec decides; without a spec the mode's defaults are used, pinning
+/// [`DEFAULT_JUCE_TAG`] of [`DEFAULT_JUCE_REPOSITORY`] where a download is involved.
+pub fn resolve_source(mode: Option<JuceMode>, spec: Option<&str>) -> Result<JuceSource> {
+    let source = spec.map(str::parse::<JuceSource>).transpose()?;
+    Ok(match (mode, source) {
+        (None, source) => source.unwrap_or_default(),
+        (Some(JuceMode::Symlink), None) => JuceSource::default(),
+        (Some(JuceMode::Symlink), Some(source @ JuceSource::Symlink { .. })) => source,
+        (Some(JuceMode::Submodule), None) => JuceSource::Submodule {
+            url: DEFAULT_JUCE_REPOSITORY.to_string(),
+            rev: Some(DEFAULT_JUCE_TAG.to_string()),
+        },
+        (Some(JuceMode::Submodule), Some(source @ JuceSource::Submodule { .. })) => source,
+        (Some(JuceMode::FetchContent), None) => JuceSource::FetchContent {
+            url: DEFAULT_JUCE_REPOSITORY.to_string(),
+            rev: Some(DEFAULT_JUCE_TAG.to_string()),
+            hash: None,
+        },
+        (Some(JuceMode::FetchContent), Some(JuceSource::Submodule { url, rev })) => JuceSource::FetchContent {
+            url,
+            rev: Some(rev.unwrap_or_else(|| DEFAULT_JUCE_TAG.to_string())),
+            hash: None,
+        },
+        (Some(JuceMode::FetchContent), Some(source @ JuceSource::FetchContent { .. })) => source,
+        (Some(mode), Some(_)) => anyhow::bail!(
+            "JUCE source '{}' cannot be used in {} mode",
+            spec.unwrap_or_default(),
+            mode.name()
+        ),
+    })
+}
+
+/// Lines of the root `CMakeLists.txt` that make JUCE available, without a trailing newline.
+pub fn juce_cmake_lines(source: &JuceSource) -> String {
+    match source {
+        JuceSource::Symlink { .. } | JuceSource::Submodule { .. } => format!("add_subdirectory({})", JUCE_SUBMODULE_PATH),
+        JuceSource::FetchContent { url, rev, hash } => {
+            let location = match (hash, rev) {
+                (Some(hash), _) => format!("    URL {}\n    URL_HASH {}", url, hash),
+                // Shallow clones only work for tags and branches
+                (None, Some(rev)) if is_commit_hash(rev) => format!("    GIT_REPOSITORY {}\n    GIT_TAG {}", url, rev),
+                (None, Some(rev)) => {
+                    format!("    GIT_REPOSITORY {}\n    GIT_TAG {}\n    GIT_SHALLOW ON", url, rev)
+                }
+                (None, None) => format!("    GIT_REPOSITORY {}\n    GIT_SHALLOW ON", url),
+            };
+            format!(
+                "include(FetchContent)  {}\nFetchContent_Declare(JUCE\n{})\nFetchContent_MakeAvailable(JUCE)",
+                FETCHCONTENT_HINT, location
+            )
+        }
+    }
+}
+
+fn is_commit_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Switches the project to obtain JUCE from `source`.
+///
+/// Removes the old symlink or submodule, sets up the new one, rewrites the JUCE lines of the
+/// root `CMakeLists.txt` and records the source in `jumake.toml`.
+pub fn switch_juce_source(context: &mut Context, source: JuceSource) -> Result<()> {
+    if context.manifest.juce == source {
+        println!("JUCE is already obtained this way, nothing to do.");
+        return Ok(());
+    }
+
+    // Check the root CMakeLists.txt before touching any files
+    let cmakelists_path = context.project_path.join("CMakeLists.txt");
+    let content = fs::read_to_string(&cmakelists_path)
+        .with_context(|| format!("Failed to read {}", cmakelists_path.display()))?;
+    let mut document = CMakeDocument::parse(&content)
+        .with_context(|| format!("Failed to parse {}", cmakelists_path.display()))?;
+
+    let juce_dir = context.project_path.join(JUCE_SUBMODULE_PATH);
+    match &context.manifest.juce {
+        JuceSource::Symlink { .. } => {
+            if juce_dir.symlink_metadata().is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+                remove_symlink(&juce_dir)?;
+            }
+        }
+        JuceSource::Submodule { .. } => remove_juce_submodule(&context.project_path)?,
+        JuceSource::FetchContent { .. } => {}
+    }
+
+    context.manifest.juce = source;
+    add_juce_submodule(context)?;
+    append_gitignore(context)?;
+
+    let old_commands = juce_commands(&document);
+    let lines = juce_cmake_lines(&context.manifest.juce);
+    if old_commands.is_empty() {
+        let add_src = document.find_command("add_subdirectory", Some("src"));
+        document.insert_command(add_src, &lines)?;
+    } else {
+        document.replace_commands(&old_commands, &lines)?;
+    }
+    fs::write(&cmakelists_path, document.into_string())
+        .with_context(|| format!("Failed to update {}", cmakelists_path.display()))?;
+    println!("Updated file: {}", cmakelists_path.display());
+
+    context.manifest.write(&context.project_path)?;
+    Ok(())
+}
+
+/// The commands of the root `CMakeLists.txt` that make JUCE available in any mode.
+fn juce_commands(document: &CMakeDocument) -> Vec<CommandId> {
+    let declare = document.find_command("FetchContent_Declare", Some("JUCE"));
+    let mut commands: Vec<CommandId> = [
+        document.find_command("add_subdirectory", Some(JUCE_SUBMODULE_PATH)),
+        declare,
+        document.find_command("FetchContent_MakeAvailable", Some("JUCE")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    // include(FetchContent) goes too, unless other dependencies are fetched
+    let fetches_others = document.find_commands("FetchContent_Declare").into_iter().any(|id| Some(id) != declare);
+    if !fetches_others {
+        commands.extend(document.find_command("include", Some("FetchContent")));
+    }
+    commands
+}
+
+/// Reads the version from `project(JUCE VERSION ...)` in the `CMakeLists.txt` of a JUCE folder.
+pub fn read_juce_version(juce_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(juce_path.join("CMakeLists.txt")).ok()?;
+    let document = CMakeDocument::parse(&content).ok()?;
+    let arguments = document.command_arguments(document.find_command("project", Some("JUCE"))?);
+    let index = arguments.iter().position(|argument| argument == "VERSION")?;
+    arguments.get(index + 1).cloned()
+}
+
+/// Registers the JUCE folder at `path` as the install `name`.
+///
+/// The first install registered becomes the default.
+pub fn add_juce_install(name: &str, path: &Path) -> Result<()> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("JUCE folder {} does not exist", path.display()))?;
+    if read_juce_version(&path).is_none() {
+        anyhow::bail!(
+            "{} is not a JUCE folder: no 'project(JUCE VERSION ...)' in its CMakeLists.txt",
+            path.display()
+        );
+    }
+
+    let mut config = load_config()?;
+    if config.juce_installs.contains_key(name) {
+        anyhow::bail!("A JUCE install named '{}' already exists", name);
+    }
+    let install = config.add_install(name, path);
+    println!(
+        "JUCE install '{}' ({}) added: {}",
+        name,
+        install.version.as_deref().unwrap_or("unknown version"),
+        install.path.display()
+    );
+    save_config(&config)?;
+    Ok(())
+}
+
+/// Unregisters the JUCE install `name`; the folder itself is left alone.
+pub fn remove_juce_install(name: &str) -> Result<()> {
+    let mut config = load_config()?;
+    if config.juce_installs.remove(name).is_none() {
+        anyhow::bail!("No JUCE install named '{}'", name);
+    }
+    if config.default_juce.as_deref() == Some(name) {
+        config.default_juce = None;
+        if let Some((next, _)) = config.default_install() {
+            println!("'{}' is now the default JUCE install.", next);
+        }
+    }
+    save_config(&config)?;
+    println!("JUCE install '{}' removed.", name);
+    Ok(())
+}
+
+/// Makes the JUCE install `name` the default for projects that don't pin one.
+pub fn set_default_juce_install(name: &str) -> Result<()> {
+    let mut config = load_config()?;
+    if !config.juce_installs.contains_key(name) {
+        anyhow::bail!("No JUCE install named '{}'", name);
+    }
+    config.default_juce = Some(name.to_string());
+    save_config(&config)?;
+    println!("'{}' is now the default JUCE install.", name);
+    Ok(())
+}
+
+/// Pins the project to a JUCE install (by name or version) and re-links `modules/JUCE`.
+pub fn use_juce_install(context: &mut Context, name_or_version: &str) -> Result<()> {
+    if !matches!(context.manifest.juce, JuceSource::Symlink { .. }) {
+        anyhow::bail!("Only projects in symlink mode use registered JUCE installs; see `jumake juce mode`");
+    }
+    let config = load_config()?;
+    let (name, install) = config
+        .find_install(name_or_version)
+        .ok_or_else(|| anyhow::anyhow!("No JUCE install with name or version '{}'", name_or_version))?;
+
+    // Pin the version so the project finds a matching install on other machines too
+    let pinned = install.version.clone().unwrap_or_else(|| name.clone());
+    context.manifest.juce = JuceSource::Symlink { version: Some(pinned.clone()) };
+    add_juce_submodule(context)?;
+    context.manifest.write(&context.project_path)?;
+    println!("Project pinned to JUCE {} ('{}' at {})", pinned, name, install.path.display());
+    Ok(())
+}
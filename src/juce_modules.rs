@@ -0,0 +1,360 @@
+// src/juce_modules.rs
+//! Managing the JUCE modules a project links against (`jumake module add/remove/list`).
+//!
+//! Modules are validated against the JUCE checkout used by the project and written to the
+//! `PRIVATE` section of `target_link_libraries(${PROJECT_NAME} ...)` in `src/CMakeLists.txt`.
+//!
+//! Custom (in-house or third-party) modules live in `modules/<name>` next to `modules/JUCE`,
+//! are declared with `juce_add_module(modules/<name>)` in the root `CMakeLists.txt` and are
+//! linked by their plain target name.
+
+use crate::build::configured_build_dirs;
+use crate::cmake_edit::{CMakeDocument, CommandId};
+use crate::context::Context;
+use crate::error::JuMakeError;
+use crate::initialize_git::{create_symlink, get_juce_path, gitignore_module};
+use crate::manifest::JuceSource;
+use anyhow::{Context as AnyhowContext, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DECLARATION_BEGIN: &str = "BEGIN_JUCE_MODULE_DECLARATION";
+const DECLARATION_END: &str = "END_JUCE_MODULE_DECLARATION";
+
+/// The `BEGIN_JUCE_MODULE_DECLARATION` block of a module header.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleDeclaration {
+    /// The `ID` field; must match the module folder name.
+    pub id: String,
+    /// Modules listed in the `dependencies` field.
+    pub dependencies: Vec<String>,
+}
+
+impl ModuleDeclaration {
+    /// Parses the declaration block of the module header `header`.
+    pub fn parse(header: &str) -> Result<Self> {
+        let start = header
+            .find(DECLARATION_BEGIN)
+            .ok_or_else(|| anyhow::anyhow!("Module header has no {} block", DECLARATION_BEGIN))?;
+        let body = &header[start + DECLARATION_BEGIN.len()..];
+        let end = body.find(DECLARATION_END).ok_or_else(|| {
+            anyhow::anyhow!("{} block is not terminated by {}", DECLARATION_BEGIN, DECLARATION_END)
+        })?;
+
+        let fields: BTreeMap<String, String> = body[..end]
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
+
+        let id = fields
+            .get("ID")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Module declaration has no ID field"))?;
+        let dependencies = fields
+            .get("dependencies")
+            .map(|value| {
+                value
+                    .split([',', ' ', '\t'])
+                    .filter(|dependency| !dependency.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self { id, dependencies })
+    }
+
+    /// Reads and parses the header of the module folder `module_path`.
+    pub fn read(module_path: &Path) -> Result<Self> {
+        let name = folder_name(module_path)?;
+        let header_path = module_path.join(format!("{}.h", name));
+        let header = fs::read_to_string(&header_path).with_context(|| {
+            format!("{} is not a JUCE module: cannot read {}", module_path.display(), header_path.display())
+        })?;
+        let declaration = Self::parse(&header)
+            .with_context(|| format!("Invalid module header {}", header_path.display()))?;
+        if declaration.id != name {
+            anyhow::bail!(
+                "Module ID '{}' in {} does not match its folder name '{}'",
+                declaration.id,
+                header_path.display(),
+                name
+            );
+        }
+        Ok(declaration)
+    }
+}
+
+/// Returns the JUCE checkout of the project: the linked `modules/JUCE` folder or the JUCE
+/// downloaded by `FetchContent` into a build directory if present, otherwise the configured
+/// JUCE path.
+pub fn juce_checkout(context: &Context) -> Result<PathBuf> {
+    let linked = context.project_path.join("modules").join("JUCE");
+    if linked.join("modules").is_dir() {
+        return Ok(linked);
+    }
+    if matches!(context.manifest.juce, JuceSource::FetchContent { .. }) {
+        let fetched = configured_build_dirs(&context.project_path)
+            .into_iter()
+            .map(|build_dir| build_dir.join("_deps").join("juce-src"))
+            .find(|fetched| fetched.join("modules").is_dir());
+        if let Some(fetched) = fetched {
+            return Ok(fetched);
+        }
+    }
+    Ok(get_juce_path()?)
+}
+
+/// Lists the modules shipped with the JUCE checkout at `juce_path`, sorted by name.
+///
+/// A module is a folder below `modules/` containing a header with the folder's name.
+pub fn available_modules(juce_path: &Path) -> Result<Vec<String>> {
+    let modules_dir = juce_path.join("modules");
+    let mut modules: Vec<String> = fs::read_dir(&modules_dir)
+        .with_context(|| format!("Cannot read JUCE modules from {}", modules_dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            entry.path().join(format!("{}.h", name)).is_file().then_some(name)
+        })
+        .collect();
+    modules.sort();
+    Ok(modules)
+}
+
+/// Lists the custom modules in the project's `modules/` folder, sorted by name.
+pub fn custom_modules(project_path: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(project_path.join("modules")) else {
+        return Vec::new();
+    };
+    let mut modules: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            (name != "JUCE" && entry.path().join(format!("{}.h", name)).is_file()).then_some(name)
+        })
+        .collect();
+    modules.sort();
+    modules
+}
+
+/// Returns the modules linked privately by the project: JUCE modules without the `juce::`
+/// prefix and custom modules by name.
+pub fn linked_modules(context: &Context) -> Result<Vec<String>> {
+    let (document, target_link_libraries, _) = load_cmakelists(context)?;
+    let custom = custom_modules(&context.project_path);
+    Ok(document
+        .section(target_link_libraries, "PRIVATE")
+        .iter()
+        .filter_map(|library| match library.strip_prefix("juce::") {
+            Some(module) => Some(module.to_string()),
+            None => custom.contains(library).then(|| library.clone()),
+        })
+        .collect())
+}
+
+/// Links the JUCE module `name` (e.g. `juce_dsp`) into the project.
+pub fn add_module(context: &Context, name: &str) -> Result<()> {
+    let name = module_name(name);
+    let juce_path = juce_checkout(context)?;
+    let available = available_modules(&juce_path)?;
+    if !available.iter().any(|module| module == name) {
+        anyhow::bail!(JuMakeError::Invalid(format!(
+            "'{}' is not a module of the JUCE checkout at {}. Available modules: {}",
+            name,
+            juce_path.display(),
+            available.join(", ")
+        )));
+    }
+
+    let (mut document, target_link_libraries, cmakelists_path) = load_cmakelists(context)?;
+    let library = format!("juce::{}", name);
+    if document.contains(target_link_libraries, "PRIVATE", &library) {
+        anyhow::bail!(JuMakeError::Invalid(format!("Module '{}' is already linked", name)));
+    }
+    document.insert_argument(target_link_libraries, "PRIVATE", &library)?;
+    write_cmakelists(&cmakelists_path, document)?;
+
+    println!("Module '{}' added successfully!", name);
+    Ok(())
+}
+
+/// Adds the custom module at `source` to the project and links it.
+///
+/// The module folder is symlinked into `modules/`, or copied if `copy` is set, declared with
+/// `juce_add_module` in the root `CMakeLists.txt` and linked into the target. A symlink is
+/// git-ignored while a copy is committed with the project. Its `dependencies` must be modules
+/// of the JUCE checkout or other custom modules.
+pub fn add_custom_module(context: &Context, source: &Path, copy: bool) -> Result<()> {
+    let source = source
+        .canonicalize()
+        .with_context(|| format!("Module folder {} does not exist", source.display()))?;
+    let declaration = ModuleDeclaration::read(&source)?;
+    let name = declaration.id.as_str();
+
+    let juce_path = juce_checkout(context)?;
+    let mut known = available_modules(&juce_path)?;
+    if known.iter().any(|module| module == name) {
+        anyhow::bail!(JuMakeError::Invalid(format!(
+            "'{}' clashes with a module of the JUCE checkout at {}",
+            name,
+            juce_path.display()
+        )));
+    }
+    known.extend(custom_modules(&context.project_path));
+    let missing: Vec<&str> = declaration
+        .dependencies
+        .iter()
+        .filter(|dependency| !known.contains(dependency))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(JuMakeError::Invalid(format!(
+            "Module '{}' depends on unknown modules: {}. Add them first.",
+            name,
+            missing.join(", ")
+        )));
+    }
+
+    // Check the CMake files before touching the modules folder
+    let (mut document, target_link_libraries, cmakelists_path) = load_cmakelists(context)?;
+    if document.contains(target_link_libraries, "PRIVATE", name) {
+        anyhow::bail!(JuMakeError::Invalid(format!("Module '{}' is already linked", name)));
+    }
+    let (mut root_document, root_cmakelists_path) = load_root_cmakelists(context)?;
+
+    let modules_path = context.project_path.join("modules");
+    let destination = modules_path.join(name);
+    let already_in_place = destination.canonicalize().is_ok_and(|existing| existing == source);
+    if !already_in_place {
+        if destination.symlink_metadata().is_ok() {
+            anyhow::bail!(JuMakeError::Invalid(format!("{} already exists", destination.display())));
+        }
+        fs::create_dir_all(&modules_path)?;
+        if copy {
+            let options = fs_extra::dir::CopyOptions::new();
+            fs_extra::dir::copy(&source, &modules_path, &options)
+                .with_context(|| format!("Failed to copy {} to {}", source.display(), modules_path.display()))?;
+            println!("Copied module: {}", destination.display());
+        } else {
+            create_symlink(&source, &destination)?;
+            println!("Linked module: {} -> {}", destination.display(), source.display());
+        }
+    }
+    // A link points into this machine, a copy belongs to the project
+    let is_link = destination.symlink_metadata().is_ok_and(|metadata| metadata.file_type().is_symlink());
+    gitignore_module(context, name, is_link)?;
+
+    if root_document.find_command("juce_add_module", Some(&module_source_dir(name))).is_none() {
+        let add_src = root_document.find_command("add_subdirectory", Some("src"));
+        root_document.insert_command(add_src, &juce_add_module_call(name))?;
+        write_cmakelists(&root_cmakelists_path, root_document)?;
+    }
+    document.insert_argument(target_link_libraries, "PRIVATE", name)?;
+    write_cmakelists(&cmakelists_path, document)?;
+
+    println!("Module '{}' added successfully!", name);
+    Ok(())
+}
+
+/// Unlinks the module `name` from the project.
+///
+/// For a custom module this also drops its `juce_add_module` call and removes its symlink from
+/// `modules/`; copied module folders are left in place.
+pub fn remove_module(context: &Context, name: &str) -> Result<()> {
+    let name = module_name(name);
+    let is_custom = custom_modules(&context.project_path).iter().any(|module| module == name);
+    let library = if is_custom { name.to_string() } else { format!("juce::{}", name) };
+
+    let (mut document, target_link_libraries, cmakelists_path) = load_cmakelists(context)?;
+    if !document.remove_argument(target_link_libraries, "PRIVATE", &library)? {
+        anyhow::bail!(JuMakeError::Invalid(format!("Module '{}' is not linked", name)));
+    }
+    write_cmakelists(&cmakelists_path, document)?;
+
+    if is_custom {
+        let (mut root_document, root_cmakelists_path) = load_root_cmakelists(context)?;
+        if let Some(call) = root_document.find_command("juce_add_module", Some(&module_source_dir(name))) {
+            root_document.remove_command(call)?;
+            write_cmakelists(&root_cmakelists_path, root_document)?;
+        }
+
+        let module_path = context.project_path.join("modules").join(name);
+        if module_path.symlink_metadata().is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            remove_symlink(&module_path)?;
+            println!("Removed link: {}", module_path.display());
+        } else {
+            println!(
+                "Note: {} was copied into the project; delete it if you no longer need it.",
+                module_path.display()
+            );
+        }
+    }
+
+    println!("Module '{}' removed successfully!", name);
+    Ok(())
+}
+
+/// The `juce_add_module` call declaring the custom module `name`.
+fn juce_add_module_call(name: &str) -> String {
+    format!("juce_add_module({})", module_source_dir(name))
+}
+
+fn module_source_dir(name: &str) -> String {
+    format!("modules/{}", name)
+}
+
+fn folder_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow::anyhow!("Invalid module folder {}", path.display()))
+}
+
+#[cfg(unix)]
+pub(crate) fn remove_symlink(path: &Path) -> Result<()> {
+    fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))
+}
+
+#[cfg(windows)]
+pub(crate) fn remove_symlink(path: &Path) -> Result<()> {
+    fs::remove_dir(path).with_context(|| format!("Failed to remove {}", path.display()))
+}
+
+/// Accepts both `juce_dsp` and `juce::juce_dsp`.
+fn module_name(name: &str) -> &str {
+    name.strip_prefix("juce::").unwrap_or(name)
+}
+
+fn load_cmakelists(context: &Context) -> Result<(CMakeDocument, CommandId, PathBuf)> {
+    let cmakelists_path = context.project_path.join("src").join("CMakeLists.txt");
+    let content = fs::read_to_string(&cmakelists_path)
+        .with_context(|| format!("Failed to read {}", cmakelists_path.display()))?;
+    let document = CMakeDocument::parse(&content)
+        .with_context(|| format!("Failed to parse {}", cmakelists_path.display()))?;
+    let target_link_libraries = document
+        .find_command("target_link_libraries", Some("${PROJECT_NAME}"))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not find 'target_link_libraries(${{PROJECT_NAME}}' in {}",
+                cmakelists_path.display()
+            )
+        })?;
+    Ok((document, target_link_libraries, cmakelists_path))
+}
+
+fn load_root_cmakelists(context: &Context) -> Result<(CMakeDocument, PathBuf)> {
+    let cmakelists_path = context.project_path.join("CMakeLists.txt");
+    let content = fs::read_to_string(&cmakelists_path)
+        .with_context(|| format!("Failed to read {}", cmakelists_path.display()))?;
+    let document = CMakeDocument::parse(&content)
+        .with_context(|| format!("Failed to parse {}", cmakelists_path.display()))?;
+    Ok((document, cmakelists_path))
+}
+
+fn write_cmakelists(path: &Path, document: CMakeDocument) -> Result<()> {
+    fs::write(path, document.into_string())
+        .with_context(|| format!("Failed to update {}", path.display()))
+}
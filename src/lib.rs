@@ -1,5 +1,22 @@
 // scr/lib.rs
 
+pub mod build;
+pub mod clean;
+pub mod cmake_edit;
+pub mod config;
 pub mod create_files;
+pub mod create_project;
 pub mod context;
+pub mod diagnostics;
+pub mod doctor;
+pub mod error;
 pub mod initialize_git;
+pub mod juce;
+pub mod juce_modules;
+pub mod manage_classes;
+pub mod manifest;
+pub mod parameters;
+pub mod placeholders;
+pub mod presets;
+pub mod regions;
+pub mod templates;
@@ -4,23 +4,55 @@
 
 use clap::{Parser, Subcommand, ValueEnum};
 use dialoguer::{theme::ColorfulTheme, Select};
-use regex::Regex;
-use std::fs;
-use std::path::{Path, PathBuf};
-use std::error::Error;
-use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
 use log::info;
 
 mod build;
+mod clean;
+mod cmake_edit;
+mod config;
 mod context;
 mod create_project;
 mod create_files;
+mod diagnostics;
+mod doctor;
+mod error;
 mod initialize_git;
+mod juce;
+mod juce_modules;
+mod manage_classes;
+mod manifest;
+mod parameters;
+mod placeholders;
+mod presets;
+mod regions;
+mod templates;
 
-use build::{build_project, run_project};
+use build::{build_project, configure_project, run_project, BuildOptions, ConfigureMode};
+use clean::{clean_project, CleanMode, CleanOptions};
 use context::Context;
+use config::{config_file_path, load_config, save_config, CONFIG_KEYS};
 use create_project::create_project;
 use create_files::add_class;
+use diagnostics::DiagnosticsFormat;
+use doctor::run_doctor;
+use error::{CliError, ErrorFormat, ErrorKind};
+use initialize_git::{is_interactive, resolve_juce_path, restore_juce, set_non_interactive, DEFAULT_COMMIT_MESSAGE};
+use juce::{
+    add_juce_install, remove_juce_install, resolve_source, set_default_juce_install, switch_juce_source,
+    use_juce_install, JuceMode,
+};
+use juce_modules::{
+    add_custom_module, add_module, available_modules, custom_modules, juce_checkout, linked_modules, remove_module,
+};
+use manage_classes::{remove_class, rename_class, EditOptions};
+use manifest::{BuildOverrides, JuceSource, ProjectManifest, BUILD_TYPES};
+use parameters::{add_parameter, ParameterSpec};
+use presets::{find_preset, generate_presets, write_presets, Preset, PRESETS_FILE};
+use templates::TemplateRegistry;
+
+/// Environment variable naming the template for `jumake new` when `--template` is not given.
+const TEMPLATE_ENV: &str = "JUMAKE_TEMPLATE";
 
 /// Main CLI parser
 #[derive(Parser)]
@@ -32,6 +64,12 @@ use create_files::add_class;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Never prompt; missing information is an error (implied when stdin is not a terminal)
+    #[arg(long, visible_alias = "yes", short = 'y', global = true)]
+    non_interactive: bool,
+    /// How to print errors; the exit code tells the kind of failure either way
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Text, global = true)]
+    error_format: ErrorFormat,
 }
 
 /// CLI subcommands
@@ -44,44 +82,309 @@ enum Commands {
         path: Option<String>,
         #[arg(short, long)]
         template: Option<String>,
+        /// Where to get JUCE: `symlink`, `git:<url>[@<tag|branch|commit>]` or
+        /// `<archive-url>#<ALGO>=<hash>`
+        #[arg(long, value_name = "SOURCE")]
+        juce: Option<String>,
+        /// How to obtain JUCE (default: symlink, or derived from --juce)
+        #[arg(long, value_enum)]
+        juce_mode: Option<JuceMode>,
+        /// Don't create the initial Git commit
+        #[arg(long)]
+        no_commit: bool,
+        /// Message of the initial Git commit
+        #[arg(long, value_name = "MESSAGE", conflicts_with = "no_commit")]
+        commit_message: Option<String>,
     },
-    /// Add a new C++ class or JUCE component
+    /// Add a new C++ class, JUCE component, other JUCE helper class or plugin parameter
     Add {
         #[arg(value_enum)]
         element_type: ElementType,
+        /// Class name, optionally prefixed with a directory below `src/` (e.g. `dsp/Filter`)
+        element_name: String,
+        /// Wrap the class in this C++ namespace (e.g. `dsp` or `dsp::filters`)
+        #[arg(short, long)]
+        namespace: Option<String>,
+        /// Parameter value range as `min..max` (param only, default `0..1`)
+        #[arg(long, allow_hyphen_values = true)]
+        range: Option<String>,
+        /// Parameter default value (param only, default is the range minimum)
+        #[arg(long, allow_negative_numbers = true)]
+        default: Option<f32>,
+        /// Parameter unit label, e.g. `dB` (param only)
+        #[arg(long)]
+        unit: Option<String>,
+        /// Also add a slider attached to the parameter to the plugin editor (param only)
+        #[arg(long)]
+        slider: bool,
+    },
+    /// Remove a class: delete its files and drop it from CMakeLists.txt
+    Remove {
+        /// Class name, optionally prefixed with its directory below `src/` (e.g. `dsp/Filter`)
         element_name: String,
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+        /// Stage the changes in Git
+        #[arg(long)]
+        stage: bool,
+    },
+    /// Rename a class: move its files and update CMakeLists.txt and #include directives
+    Rename {
+        /// Current class name, optionally prefixed with its directory below `src/`
+        old_name: String,
+        /// New class name; prefix it with a directory to move the class as well
+        new_name: String,
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+        /// Stage the changes in Git
+        #[arg(long)]
+        stage: bool,
+    },
+    /// Add, remove or list the JUCE modules linked into the project
+    Module {
+        #[command(subcommand)]
+        action: ModuleAction,
+    },
+    /// Manage how the project obtains JUCE
+    Juce {
+        #[command(subcommand)]
+        action: JuceAction,
     },
     /// Build the project
+    ///
+    /// The options from `-D` to the arguments after `--` are saved in jumake.toml and reused by
+    /// later builds until `--reset-build-options`.
     Build {
-        #[arg(short = 't', long = "build-type", default_value_t = String::from("Release"))]
-        build_type: String,
+        /// Build type; also becomes the project's default in jumake.toml
+        #[arg(short = 't', long = "build-type")]
+        build_type: Option<String>,
+        /// Configure and build with a preset from CMakePresets.json or CMakeUserPresets.json
+        #[arg(long, value_name = "NAME", conflicts_with = "build_type")]
+        preset: Option<String>,
+        /// Run the configure step even if the build directory is up to date
+        #[arg(long)]
+        reconfigure: bool,
+        /// How to report compiler diagnostics; `json` and `gcc` print them on stdout and move
+        /// the build output to stderr
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = DiagnosticsFormat::Text)]
+        diagnostics: DiagnosticsFormat,
+        /// Set a CMake cache variable, e.g. `-D JUCE_BUILD_EXTRAS=ON`; can be repeated
+        #[arg(short = 'D', value_name = "KEY=VALUE", conflicts_with = "preset")]
+        define: Vec<String>,
+        /// CMake toolchain file
+        #[arg(long, value_name = "FILE", conflicts_with = "preset")]
+        toolchain: Option<PathBuf>,
+        /// CMake generator, e.g. `Ninja` or `Xcode`
+        #[arg(short = 'G', long, value_name = "NAME", conflicts_with = "preset")]
+        generator: Option<String>,
+        /// Build only this target
+        #[arg(long, value_name = "NAME")]
+        target: Option<String>,
+        /// Number of parallel build jobs
+        #[arg(short, long, value_name = "N")]
+        jobs: Option<usize>,
+        /// Arguments passed on to `cmake --build`
+        #[arg(last = true, value_name = "ARGS")]
+        build_args: Vec<String>,
+        /// Forget the options saved by earlier builds (all but the build type) before applying
+        /// the ones given
+        #[arg(long)]
+        reset_build_options: bool,
+    },
+    /// Run the CMake configure step, even if the build directory is up to date
+    Configure {
+        /// Build type (default: the project's default from jumake.toml)
+        #[arg(short = 't', long = "build-type")]
+        build_type: Option<String>,
+        /// Configure with a preset from CMakePresets.json or CMakeUserPresets.json
+        #[arg(long, value_name = "NAME", conflicts_with = "build_type")]
+        preset: Option<String>,
+        /// Discard the CMake cache and configure from scratch
+        #[arg(long)]
+        fresh: bool,
+    },
+    /// Delete build outputs, or with --cache or --all the CMake caches or whole build directories
+    Clean {
+        /// Delete only the CMake cache (CMakeCache.txt and CMakeFiles/) of each build directory, so
+        /// the next build configures from scratch
+        #[arg(long, conflicts_with = "all")]
+        cache: bool,
+        /// Delete jumake_build/, the copied compile_commands.json and the legacy .jumake file
+        #[arg(long)]
+        all: bool,
+        /// Only list what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write CMakePresets.json with a configure and build preset per build type
+    Presets {
+        /// Overwrite an existing CMakePresets.json
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check the build tools, JUCE installs and build directory, suggesting fixes
+    Doctor {
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
     },
     /// Build and run the project
     Run {
-        #[arg(short = 't', long = "build-type", default_value = "LastUsed")]
-        build_type: String,
+        /// Build type (default: the project's default from jumake.toml)
+        #[arg(short = 't', long = "build-type")]
+        build_type: Option<String>,
+    },
+    /// Show or change the global JuMake configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+/// `jumake module` subcommands
+#[derive(Subcommand)]
+enum ModuleAction {
+    /// Link a JUCE module (e.g. `juce_dsp`), or a custom module with `--path`, into the project
+    Add {
+        /// Name of a module of the JUCE checkout
+        #[arg(required_unless_present = "path", conflicts_with = "path")]
+        name: Option<String>,
+        /// Folder of a custom JUCE-format module to add under `modules/`
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Copy the custom module into the project instead of symlinking it
+        #[arg(long, requires = "path")]
+        copy: bool,
+    },
+    /// Unlink a JUCE module from the project
+    Remove { name: String },
+    /// List the JUCE and custom modules, marking the linked ones
+    List,
+}
+
+/// `jumake juce` subcommands
+#[derive(Subcommand)]
+enum JuceAction {
+    /// List the registered JUCE installs
+    List,
+    /// Register a local JUCE folder as a named install
+    Add {
+        /// Name of the install, e.g. `juce7`
+        name: String,
+        /// Path to the JUCE folder
+        path: PathBuf,
+    },
+    /// Unregister a JUCE install (the folder is kept)
+    Remove { name: String },
+    /// Make an install the default for projects that don't pin one
+    Default { name: String },
+    /// Pin the current project to an install (by name or version) and re-link modules/JUCE
+    Use { install: String },
+    /// Switch the project to another way of obtaining JUCE, rewriting the root CMakeLists.txt
+    Mode {
+        #[arg(value_enum)]
+        mode: JuceMode,
+        /// Where to get JUCE: `git:<url>[@<rev>]` or `<archive-url>#<ALGO>=<hash>`
+        #[arg(long, value_name = "SOURCE")]
+        source: Option<String>,
     },
 }
 
+/// `jumake config` subcommands
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the value of a setting
+    Get { key: String },
+    /// Change a setting
+    Set { key: String, value: String },
+    /// Remove a setting
+    Unset { key: String },
+    /// List all settings
+    List,
+    /// Print the location of the configuration file
+    Path,
+}
+
 /// Strongly-typed element type for `Add` command
 #[derive(ValueEnum, Clone, Debug)]
 enum ElementType {
+    /// Plain C++ class
     Class,
+    /// `juce::Component` subclass
     Component,
+    /// `juce::dsp::ProcessorBase` subclass with prepare/process/reset
+    Processor,
+    /// `juce::AudioProcessorEditor` subclass
+    Editor,
+    /// `juce::LookAndFeel_V4` subclass
+    LookAndFeel,
+    /// `juce::Thread` subclass
+    Thread,
+    /// `juce::Timer` subclass
+    Timer,
+    /// `juce::AudioParameterFloat` in an AudioPlugin project
+    Param,
 }
 
 fn main() {
     env_logger::init(); // Initialize logger
     let cli = Cli::parse();
+    set_non_interactive(cli.non_interactive);
 
     // Execute selected command and handle errors gracefully
     if let Err(e) = match cli.command {
-        Commands::New { project_name, path, template } => handle_new(project_name, path, template),
-        Commands::Add { element_type, element_name } => handle_add(element_type, element_name),
-        Commands::Build { build_type } => handle_build(build_type),
+        Commands::New { project_name, path, template, juce, juce_mode, no_commit, commit_message } => {
+            // None skips the initial commit
+            let commit_message =
+                (!no_commit).then(|| commit_message.unwrap_or_else(|| DEFAULT_COMMIT_MESSAGE.into()));
+            handle_new(project_name, path, template, juce, juce_mode, commit_message)
+        }
+        Commands::Add { element_type, element_name, namespace, range, default, unit, slider } => {
+            handle_add(element_type, element_name, namespace, range, default, unit, slider)
+        }
+        Commands::Remove { element_name, dry_run, stage } => {
+            handle_remove(element_name, EditOptions { dry_run, stage })
+        }
+        Commands::Rename { old_name, new_name, dry_run, stage } => {
+            handle_rename(old_name, new_name, EditOptions { dry_run, stage })
+        }
+        Commands::Module { action } => handle_module(action),
+        Commands::Juce { action } => handle_juce(action),
+        Commands::Configure { build_type, preset, fresh } => handle_configure(build_type, preset, fresh),
+        Commands::Build {
+            build_type,
+            preset,
+            reconfigure,
+            diagnostics,
+            define,
+            toolchain,
+            generator,
+            target,
+            jobs,
+            build_args,
+            reset_build_options,
+        } => {
+            let overrides = BuildOverrides {
+                define,
+                toolchain,
+                generator,
+                target,
+                jobs,
+                build_args,
+                reset: reset_build_options,
+            };
+            handle_build(build_type, preset, reconfigure, diagnostics, overrides)
+        }
+        Commands::Clean { cache, all, dry_run } => handle_clean(cache, all, dry_run),
+        Commands::Presets { force } => handle_presets(force),
         Commands::Run { build_type } => handle_run(build_type),
+        Commands::Doctor { json } => handle_doctor(json),
+        Commands::Config { action } => handle_config(action),
     } {
-        eprintln!("❌ Error: {}", e);
+        e.report(cli.error_format);
+        std::process::exit(e.kind.exit_code());
     }
 }
 
@@ -89,159 +392,401 @@ fn main() {
 // Command handlers
 // ------------------------
 
-fn handle_new(project_name: String, path: Option<String>, template: Option<String>) -> Result<(), Box<dyn Error>> {
+fn handle_new(
+    project_name: String,
+    path: Option<String>,
+    template: Option<String>,
+    juce: Option<String>,
+    juce_mode: Option<JuceMode>,
+    commit_message: Option<String>,
+) -> Result<(), CliError> {
     // Determine project path
-    let project_path = path
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().expect("Failed to get cwd"))
-        .join(&project_name);
+    let parent = match path {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_dir()?,
+    };
+    let project_path = parent.join(&project_name);
 
-    // Use provided template or prompt user
-    let template_name = template.or_else(|| select_template());
+    // Use provided template, then JUMAKE_TEMPLATE, then the configured default, or prompt user
+    let config = load_config()?;
+    let registry = TemplateRegistry::load()?;
+    let template = template
+        .or_else(|| std::env::var(TEMPLATE_ENV).ok().filter(|name| !name.is_empty()))
+        .or_else(|| config.default_template.clone());
+    let template_name = match template {
+        Some(name) if registry.get(&name).is_none() => {
+            return Err(CliError::usage(format!(
+                "Unknown template: {}. Available templates: {}",
+                name,
+                registry.names().join(", ")
+            )));
+        }
+        Some(name) => Some(name),
+        None if !is_interactive() => {
+            return Err(CliError::usage(format!(
+                "No template given: pass --template or set {}. Available templates: {}",
+                TEMPLATE_ENV,
+                registry.names().join(", ")
+            )));
+        }
+        None => select_template(&registry),
+    };
 
+    let mut manifest = ProjectManifest::new(&project_name, template_name.clone());
+    manifest.juce = resolve_source(juce_mode, juce.as_deref())?;
+    if let Some(build_type) = &config.default_build_type {
+        manifest.build.build_type = build_type.clone();
+    }
+    // Pin the default JUCE install's version so the project keeps using it
+    if let JuceSource::Symlink { version: version @ None } = &mut manifest.juce {
+        *version = config.default_install().and_then(|(_, install)| install.version.clone());
+    }
+    // Find JUCE (or ask for it) before any files are created
+    if let JuceSource::Symlink { version } = &manifest.juce {
+        resolve_juce_path(version.as_deref())?;
+    }
     let context = Context {
         project_name,
         project_path,
         template_name,
-        build_type: "Release".to_string(),
+        build_type: manifest.build.build_type.clone(),
+        manifest,
     };
 
-    create_project(&context)?;
-    info!("✅ Project created successfully at {}", context.project_path.display());
+    create_project(&context, commit_message.as_deref())?;
+    println!("✅ Project created successfully at {}", context.project_path.display());
     Ok(())
 }
 
-fn handle_add(element_type: ElementType, element_name: String) -> Result<(), Box<dyn Error>> {
+fn handle_add(
+    element_type: ElementType,
+    element_name: String,
+    namespace: Option<String>,
+    range: Option<String>,
+    default: Option<f32>,
+    unit: Option<String>,
+    slider: bool,
+) -> Result<(), CliError> {
     let context = current_context()?;
+
+    let has_param_options = range.is_some() || default.is_some() || unit.is_some() || slider;
+    if has_param_options && !matches!(element_type, ElementType::Param) {
+        return Err(CliError::usage("--range, --default, --unit and --slider only apply to `add param`"));
+    }
+
     // map the enum to the lowercase strings expected by add_class
     let element_type_str = match element_type {
         ElementType::Class => "class",
         ElementType::Component => "component",
+        ElementType::Processor => "processor",
+        ElementType::Editor => "editor",
+        ElementType::LookAndFeel => "look-and-feel",
+        ElementType::Thread => "thread",
+        ElementType::Timer => "timer",
+        ElementType::Param => {
+            if namespace.is_some() {
+                return Err(CliError::usage("--namespace does not apply to `add param`"));
+            }
+            let spec = ParameterSpec::new(&element_name, range.as_deref(), default, unit, slider)?;
+            add_parameter(&context, &spec)?;
+            info!("✅ Added parameter: {}", element_name);
+            return Ok(());
+        }
     };
-    add_class(&context, element_type_str, &element_name)?;
+    add_class(&context, element_type_str, &element_name, namespace.as_deref())?;
     info!("✅ Added {}: {}", element_type_str, element_name);
     Ok(())
 }
 
-fn handle_build(build_type: String) -> Result<(), Box<dyn Error>> {
-    validate_build_type(&build_type)?;
+fn handle_remove(element_name: String, options: EditOptions) -> Result<(), CliError> {
+    let context = current_context()?;
+    remove_class(&context, &element_name, options)?;
+    info!("✅ Removed: {}", element_name);
+    Ok(())
+}
 
-    let context = current_context_with_build(&build_type)?;
-    build_project(&context)?;
-    save_build_type(&context)?;
-    info!("✅ Build succeeded: {}", build_type);
+fn handle_rename(old_name: String, new_name: String, options: EditOptions) -> Result<(), CliError> {
+    let context = current_context()?;
+    rename_class(&context, &old_name, &new_name, options)?;
+    info!("✅ Renamed: {} → {}", old_name, new_name);
     Ok(())
 }
 
-fn handle_run(build_type: String) -> Result<(), Box<dyn Error>> {
-    let project_path = std::env::current_dir()?;
-// Use last build type if requested
-    let effective_build_type = if build_type == "LastUsed" {
-        read_last_build_type(&project_path).unwrap_or_else(|| "Release".to_string())
-    } else {
-        build_type
-    };
+fn handle_module(action: ModuleAction) -> Result<(), CliError> {
+    let context = current_context()?;
+    match action {
+        ModuleAction::Add { name: Some(name), .. } => {
+            add_module(&context, &name)?;
+            info!("✅ Linked module: {}", name);
+        }
+        ModuleAction::Add { path: Some(path), copy, .. } => {
+            add_custom_module(&context, &path, copy)?;
+            info!("✅ Linked custom module: {}", path.display());
+        }
+        ModuleAction::Add { .. } => return Err(CliError::usage("Specify a module name or --path")),
+        ModuleAction::Remove { name } => {
+            remove_module(&context, &name)?;
+            info!("✅ Unlinked module: {}", name);
+        }
+        ModuleAction::List => {
+            let linked = linked_modules(&context)?;
+            let juce_path = juce_checkout(&context)?;
+            println!("JUCE modules in {}:", juce_path.display());
+            for module in available_modules(&juce_path)? {
+                let marker = if linked.contains(&module) { "*" } else { " " };
+                println!("  {} {}", marker, module);
+            }
+            let custom = custom_modules(&context.project_path);
+            if !custom.is_empty() {
+                println!("Custom modules in {}:", context.project_path.join("modules").display());
+                for module in custom {
+                    let marker = if linked.contains(&module) { "*" } else { " " };
+                    println!("  {} {}", marker, module);
+                }
+            }
+            println!("(* = linked)");
+        }
+    }
+    Ok(())
+}
 
-    validate_build_type(&effective_build_type)?;
+fn handle_juce(action: JuceAction) -> Result<(), CliError> {
+    match action {
+        JuceAction::List => {
+            let config = load_config()?;
+            if config.juce_installs.is_empty() {
+                println!("No JUCE installs registered. Add one with `jumake juce add <name> <path>`.");
+            }
+            let default = config.default_install().map(|(name, _)| name.clone());
+            for (name, install) in &config.juce_installs {
+                let marker = if default.as_ref() == Some(name) { "*" } else { " " };
+                let missing = if install.path.is_dir() { "" } else { " (missing)" };
+                println!(
+                    "  {} {:<16} {:<10} {}{}",
+                    marker,
+                    name,
+                    install.version.as_deref().unwrap_or("?"),
+                    install.path.display(),
+                    missing
+                );
+            }
+            if default.is_some() {
+                println!("(* = default)");
+            }
+        }
+        JuceAction::Add { name, path } => add_juce_install(&name, &path).map_err(config_error)?,
+        JuceAction::Remove { name } => remove_juce_install(&name).map_err(config_error)?,
+        JuceAction::Default { name } => set_default_juce_install(&name).map_err(config_error)?,
+        JuceAction::Use { install } => {
+            let mut context = current_context()?;
+            use_juce_install(&mut context, &install).map_err(config_error)?;
+        }
+        JuceAction::Mode { mode, source } => {
+            let mut context = current_context()?;
+            let source = resolve_source(Some(mode), source.as_deref())?;
+            switch_juce_source(&mut context, source)?;
+            info!("✅ JUCE mode switched to {:?}", mode);
+        }
+    }
+    Ok(())
+}
 
-    let project_name = extract_project_name(project_path.join("CMakeLists.txt"))?;
-    let context = Context {
-        project_name,
-        project_path: project_path.clone(),
-        template_name: determine_template_name(&project_path),
-        build_type: effective_build_type,
+fn handle_configure(build_type: Option<String>, preset: Option<String>, fresh: bool) -> Result<(), CliError> {
+    let (context, preset) = build_context(build_type.as_deref(), preset.as_deref())?;
+    let configure = if fresh { ConfigureMode::Fresh } else { ConfigureMode::Always };
+    let build_dir = configure_project(&context, &BuildOptions { preset, configure, ..Default::default() })?;
+    println!("✅ Configured {}", build_dir.display());
+    Ok(())
+}
+
+fn handle_build(
+    build_type: Option<String>,
+    preset: Option<String>,
+    reconfigure: bool,
+    diagnostics: DiagnosticsFormat,
+    overrides: BuildOverrides,
+) -> Result<(), CliError> {
+    let (mut context, preset) = build_context(build_type.as_deref(), preset.as_deref())?;
+    let saved = context.manifest.clone();
+    context.manifest.build.apply(overrides, &context.project_path)?;
+    let in_effect = context.manifest.build.build_step_args();
+    let remembered: Vec<String> =
+        saved.build.build_step_args().into_iter().filter(|arg| in_effect.contains(arg)).collect();
+    note_saved_build_options(&remembered);
+    // The preset decides the build type; the project's default stays as it is
+    let save_default = preset.is_none();
+    let configure = if reconfigure { ConfigureMode::Always } else { ConfigureMode::Auto };
+    build_project(&context, &BuildOptions { diagnostics, preset, configure })?;
+    if save_default {
+        context.manifest.build.build_type = context.build_type.clone();
+    }
+    if context.manifest != saved {
+        context.manifest.write(&context.project_path)?;
+    }
+    // CMakePresets.json is only written on request, to keep changes made to it
+    let presets_changed = generate_presets(&context.manifest) != generate_presets(&saved);
+    if presets_changed && context.project_path.join(PRESETS_FILE).exists() {
+        eprintln!("ℹ️  Run `jumake presets --force` to update {} with the new settings.", PRESETS_FILE);
+    }
+    info!("✅ Build succeeded: {}", context.build_type);
+    Ok(())
+}
+
+fn handle_clean(cache: bool, all: bool, dry_run: bool) -> Result<(), CliError> {
+    let context = current_context()?;
+    let mode = match (cache, all) {
+        (_, true) => CleanMode::All,
+        (true, false) => CleanMode::Cache,
+        (false, false) => CleanMode::Outputs,
     };
+    clean_project(&context.project_path, CleanOptions { mode, dry_run })?;
+    if !dry_run {
+        info!("✅ Clean completed.");
+    }
+    Ok(())
+}
 
-    run_project(&context)?;
+fn handle_presets(force: bool) -> Result<(), CliError> {
+    let context = current_context()?;
+    if context.project_path.join(PRESETS_FILE).exists() && !force {
+        return Err(CliError::usage(format!("{} already exists; use --force to overwrite it", PRESETS_FILE)));
+    }
+    write_presets(&context)?;
+    println!("✅ Wrote {}", PRESETS_FILE);
+    Ok(())
+}
+
+fn handle_run(build_type: Option<String>) -> Result<(), CliError> {
+    let context = current_context_with_build(build_type.as_deref())?;
+    restore_juce(&context)?;
+    note_saved_build_options(&context.manifest.build.build_step_args());
+    run_project(&context, &BuildOptions::default())?;
     info!("✅ Run completed.");
     Ok(())
 }
 
+fn handle_config(action: ConfigAction) -> Result<(), CliError> {
+    match action {
+        ConfigAction::Get { key } => match load_config()?.get(&key).map_err(config_error)? {
+            Some(value) => println!("{}", value),
+            None => return Err(config_error(anyhow::anyhow!("'{}' is not set", key))),
+        },
+        ConfigAction::Set { key, value } => {
+            let mut config = load_config()?;
+            config.set(&key, &value).map_err(config_error)?;
+            save_config(&config)?;
+            println!("{} = {}", key, config.get(&key)?.unwrap_or_default());
+        }
+        ConfigAction::Unset { key } => {
+            let mut config = load_config()?;
+            config.unset(&key).map_err(config_error)?;
+            save_config(&config)?;
+            println!("'{}' unset.", key);
+        }
+        ConfigAction::List => {
+            let config = load_config()?;
+            for (key, description) in CONFIG_KEYS {
+                match config.get(key)? {
+                    Some(value) => println!("{} = {}", key, value),
+                    None => println!("{} (not set) — {}", key, description),
+                }
+            }
+            if !config.juce_installs.is_empty() {
+                println!("JUCE installs: see `jumake juce list`");
+            }
+        }
+        ConfigAction::Path => println!("{}", config_file_path()?.display()),
+    }
+    Ok(())
+}
+
+fn handle_doctor(json: bool) -> Result<(), CliError> {
+    let report = run_doctor(std::env::current_dir().ok().as_deref());
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        report.print();
+    }
+    match report.count(doctor::Status::Error) {
+        0 => Ok(()),
+        errors => Err(CliError::new(ErrorKind::Other, anyhow::anyhow!("doctor found {} problem(s)", errors))),
+    }
+}
+
 // ------------------------
 // Helpers
 // ------------------------
 
-/// Get current context using working directory
-fn current_context() -> Result<Context, Box<dyn Error>> {
-    let project_path = std::env::current_dir()?;
-    Ok(Context {
-        project_name: project_path.file_name().unwrap().to_string_lossy().to_string(),
-        project_path,
-        template_name: None,
-        build_type: "Release".to_string(),
-    })
+/// Marks an error of the global configuration or JUCE install management
+fn config_error(error: anyhow::Error) -> CliError {
+    CliError::new(ErrorKind::Config, error)
 }
 
-/// Get current context with specified build type
-fn current_context_with_build(build_type: &str) -> Result<Context, Box<dyn Error>> {
-    let project_path = std::env::current_dir()?;
-    Ok(Context {
-        project_name: project_path.file_name().unwrap().to_string_lossy().to_string(),
-        project_path: project_path.clone(),
-        template_name: determine_template_name(&project_path),
-        build_type: build_type.to_string(),
-    })
+/// Get current context from the jumake.toml of the working directory
+fn current_context() -> Result<Context, CliError> {
+    Context::load(std::env::current_dir()?).map_err(config_error)
+}
+
+/// Get the context for configuring or building, with JUCE restored, and the selected preset,
+/// whose build type (if any) replaces the project's default
+fn build_context(build_type: Option<&str>, preset: Option<&str>) -> Result<(Context, Option<Preset>), CliError> {
+    let mut context = current_context_with_build(build_type)?;
+    restore_juce(&context)?;
+    let Some(preset) = preset else {
+        return Ok((context, None));
+    };
+    let preset = find_preset(&context.project_path, preset).map_err(config_error)?;
+    if let Some(build_type) = &preset.build_type {
+        context.build_type = build_type.clone();
+    }
+    Ok((context, Some(preset)))
+}
+
+/// Get current context, overriding the project's default build type if one is given
+fn current_context_with_build(build_type: Option<&str>) -> Result<Context, CliError> {
+    let mut context = current_context()?;
+    if let Some(build_type) = build_type {
+        context.build_type = build_type.to_string();
+    }
+    validate_build_type(&context.build_type).map_err(CliError::usage)?;
+    Ok(context)
 }
 
 /// Prompt user to select a template interactively
-fn select_template() -> Option<String> {
-    let options = ["GuiApplication", "AudioPlugin", "ConsoleApp"];
+fn select_template(registry: &TemplateRegistry) -> Option<String> {
+    let templates = registry.templates();
+    let options: Vec<String> = templates
+        .iter()
+        .map(|t| if t.description.is_empty() {
+            t.name.clone()
+        } else {
+            format!("{} — {}", t.name, t.description)
+        })
+        .collect();
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Select a template:")
         .default(0)
         .items(&options)
         .interact()
         .ok()?;
-    Some(options[selection].to_string())
+    Some(templates[selection].name.clone())
 }
 
 /// Validate build type string
 fn validate_build_type(build_type: &str) -> Result<(), String> {
-    match build_type {
-        "Debug" | "Release" | "RelWithDebInfo" | "MinSizeRel" => Ok(()),
-        _ => Err(format!(
-            "Invalid build type: {}. Valid options: Debug, Release, RelWithDebInfo, MinSizeRel",
-            build_type
-        )),
-    }
-}
-
-/// Persist last used build type
-fn save_build_type(context: &Context) -> std::io::Result<()> {
-    fs::write(context.project_path.join(".jumake"), &context.build_type)
-}
-
-/// Read last used build type
-fn read_last_build_type(project_path: &Path) -> Option<String> {
-    fs::read_to_string(project_path.join(".jumake")).ok()
-}
-
-/// Determine template name from CMakeLists.txt
-fn determine_template_name(project_path: &Path) -> Option<String> {
-    let cmakelists_path = project_path.join("src").join("CMakeLists.txt");
-    if cmakelists_path.exists() {
-        let content = fs::read_to_string(&cmakelists_path).unwrap_or_default();
-        let re = Regex::new(r#"set\(JUMAKE_TEMPLATE\s+"([^"]+)"\)"#).unwrap();
-        if let Some(caps) = re.captures(&content) {
-            return Some(caps[1].to_string());
-        }
+    if BUILD_TYPES.contains(&build_type) {
+        Ok(())
+    } else {
+        Err(format!("Invalid build type: {}. Valid options: {}", build_type, BUILD_TYPES.join(", ")))
     }
-    Some("GuiApplication".to_string())
 }
 
-/// Extract project name from CMakeLists.txt
-fn extract_project_name<P: AsRef<Path>>(cmake_file_path: P) -> Result<String, Box<dyn Error>> {
-    let file = fs::File::open(cmake_file_path)?;
-    for line in BufReader::new(file).lines() {
-        let line = line?;
-        if let Some(stripped) = line.trim_start().strip_prefix("project(") {
-            if let Some(end) = stripped.find(')') {
-                let content = &stripped[..end];
-                if let Some(name) = content.split_whitespace().next() {
-                    return Ok(name.to_string());
-                }
-            }
-        }
+/// Point out options of earlier builds that still narrow down this one
+fn note_saved_build_options(remembered: &[String]) {
+    if !remembered.is_empty() {
+        eprintln!(
+            "ℹ️  Using build options saved in jumake.toml: {} (clear them with `jumake build --reset-build-options`)",
+            remembered.join(" ")
+        );
     }
-    Err("Project name not found in CMakeLists.txt".into())
 }
@@ -0,0 +1,338 @@
+// src/manage_classes.rs
+//! Removing and renaming classes previously added with `jumake add`.
+//!
+//! Both operations are first collected into a plan of file system changes, which is
+//! either printed (`--dry-run`) or applied and optionally staged in Git.
+
+use crate::context::Context;
+use crate::create_files::{add_cmake_source, cmake_source_entry, remove_cmake_source, split_element_path};
+use crate::diagnostics::normalize;
+use crate::error::JuMakeError;
+use anyhow::{Context as AnyhowContext, Result};
+use git2::Repository;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File extensions scanned for `#include` directives and class name references.
+const SOURCE_EXTENSIONS: &[&str] = &["h", "hpp", "hxx", "c", "cc", "cpp", "cxx"];
+
+/// Options shared by `remove` and `rename`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EditOptions {
+    /// Only print what would change.
+    pub dry_run: bool,
+    /// Stage all changes in the project's Git repository.
+    pub stage: bool,
+}
+
+/// A single file system change.
+#[derive(Debug)]
+enum Change {
+    Delete(PathBuf),
+    Move { from: PathBuf, to: PathBuf },
+    Write { path: PathBuf, content: String },
+}
+
+/// Removes a class: deletes its `.h`/`.cpp` files and drops the source from `CMakeLists.txt`.
+///
+/// `element_name` is the class name, optionally prefixed with its directory below `src/`
+/// (e.g. `dsp/FilterProcessor`).
+pub fn remove_class(context: &Context, element_name: &str, options: EditOptions) -> Result<()> {
+    let src_path = context.project_path.join("src");
+    let (sub_dir, class_name) = split_element_path(element_name)?;
+    let (header_path, cpp_path) = class_files(&src_path, &sub_dir, class_name)?;
+
+    let mut changes = Vec::new();
+    if cpp_path.exists() {
+        let (cmake_dir, entry) = cmake_source_entry(&src_path, &cpp_path);
+        let cmakelists_path = cmake_dir.join("CMakeLists.txt");
+        let content = read(&cmakelists_path)?;
+        changes.push(Change::Write {
+            content: remove_cmake_source(&content, &entry)
+                .with_context(|| format!("Cannot update {}", cmakelists_path.display()))?,
+            path: cmakelists_path,
+        });
+        changes.push(Change::Delete(cpp_path.clone()));
+    }
+    if header_path.exists() {
+        changes.push(Change::Delete(header_path.clone()));
+    }
+
+    execute(context, &changes, options)?;
+
+    // Includes of the removed header are left for the user to resolve
+    for file in source_files(&src_path)? {
+        if file == header_path || file == cpp_path {
+            continue;
+        }
+        let content = read(&file)?;
+        if includes_of(&content, &file, &src_path, &header_path).next().is_some() {
+            eprintln!("Warning: {} still includes {}.h", file.display(), class_name);
+        }
+    }
+
+    if !options.dry_run {
+        println!("Class '{}' removed successfully!", class_name);
+    }
+    Ok(())
+}
+
+/// Renames a class: moves its files, rewrites the class name inside them, updates
+/// `CMakeLists.txt` and every `#include` of the header below `src/`.
+///
+/// If `new_name` has no directory part, the class stays in its current directory.
+pub fn rename_class(context: &Context, old_name: &str, new_name: &str, options: EditOptions) -> Result<()> {
+    let src_path = context.project_path.join("src");
+    let (old_dir, old_class) = split_element_path(old_name)?;
+    let (new_dir, new_class) = split_element_path(new_name)?;
+    let new_dir = if new_name.contains('/') { new_dir } else { old_dir.clone() };
+
+    let (old_header, old_cpp) = class_files(&src_path, &old_dir, old_class)?;
+    let new_header = src_path.join(&new_dir).join(format!("{}.h", new_class));
+    let new_cpp = src_path.join(&new_dir).join(format!("{}.cpp", new_class));
+    if new_header.exists() || new_cpp.exists() {
+        anyhow::bail!(JuMakeError::Invalid(format!("Class '{}' already exists in the project.", new_name)));
+    }
+
+    let class_pattern = Regex::new(&format!(r"\b{}\b", regex::escape(old_class)))?;
+    let mut changes = Vec::new();
+
+    // Update target_sources in the responsible CMakeLists.txt (possibly two different ones)
+    if old_cpp.exists() {
+        let (old_cmake_dir, old_entry) = cmake_source_entry(&src_path, &old_cpp);
+        let (new_cmake_dir, new_entry) = cmake_source_entry(&src_path, &new_cpp);
+        let old_cmakelists = old_cmake_dir.join("CMakeLists.txt");
+        let new_cmakelists = new_cmake_dir.join("CMakeLists.txt");
+
+        let content = remove_cmake_source(&read(&old_cmakelists)?, &old_entry)
+            .with_context(|| format!("Cannot update {}", old_cmakelists.display()))?;
+        if old_cmakelists == new_cmakelists {
+            changes.push(Change::Write { path: old_cmakelists, content: add_cmake_source(&content, &new_entry)? });
+        } else {
+            let new_content = add_cmake_source(&read(&new_cmakelists)?, &new_entry)
+                .with_context(|| format!("Cannot update {}", new_cmakelists.display()))?;
+            changes.push(Change::Write { path: old_cmakelists, content });
+            changes.push(Change::Write { path: new_cmakelists, content: new_content });
+        }
+    }
+
+    // Move the class files and rewrite the class name inside them
+    for (from, to) in [(&old_header, &new_header), (&old_cpp, &new_cpp)] {
+        if !from.exists() {
+            continue;
+        }
+        let content = rewrite_includes(&read(from)?, from, to, &src_path, &old_header, &new_header);
+        // Rename the class everywhere except in include paths, which were handled above
+        let content: String = content
+            .split_inclusive('\n')
+            .map(|line| match parse_include(line) {
+                Some(_) => line.into(),
+                None => class_pattern.replace_all(line, new_class),
+            })
+            .collect();
+        changes.push(Change::Move { from: from.clone(), to: to.clone() });
+        changes.push(Change::Write { path: to.clone(), content });
+    }
+
+    // Rewrite includes of the header in all other source files
+    let mut still_referenced = Vec::new();
+    for file in source_files(&src_path)? {
+        if file == old_header || file == old_cpp {
+            continue;
+        }
+        let content = read(&file)?;
+        let rewritten = rewrite_includes(&content, &file, &file, &src_path, &old_header, &new_header);
+        if class_pattern.is_match(&rewritten) {
+            still_referenced.push(file.clone());
+        }
+        if rewritten != content {
+            changes.push(Change::Write { path: file, content: rewritten });
+        }
+    }
+
+    execute(context, &changes, options)?;
+
+    for file in still_referenced {
+        eprintln!("Warning: {} still refers to '{}'", file.display(), old_class);
+    }
+    if !options.dry_run {
+        println!("Class '{}' renamed to '{}' successfully!", old_class, new_class);
+    }
+    Ok(())
+}
+
+/// Returns the header and source paths of a class, failing if neither exists.
+fn class_files(src_path: &Path, sub_dir: &Path, class_name: &str) -> Result<(PathBuf, PathBuf)> {
+    let header = src_path.join(sub_dir).join(format!("{}.h", class_name));
+    let cpp = src_path.join(sub_dir).join(format!("{}.cpp", class_name));
+    if !header.exists() && !cpp.exists() {
+        anyhow::bail!(JuMakeError::Invalid(format!(
+            "Class '{}' not found in {}",
+            class_name,
+            src_path.join(sub_dir).display()
+        )));
+    }
+    Ok((header, cpp))
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Recursively collects all C/C++ source files below `dir`, in sorted order.
+fn source_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Iterates over the quoted `#include` paths in `content` (of the file at `file`) that
+/// resolve to `header`, either relative to the including file or to `src_path`.
+fn includes_of<'a>(
+    content: &'a str,
+    file: &'a Path,
+    src_path: &'a Path,
+    header: &'a Path,
+) -> impl Iterator<Item = &'a str> + 'a {
+    content.lines().filter_map(move |line| {
+        let include = parse_include(line)?;
+        let base = file.parent()?;
+        (normalize(&base.join(include)) == header || normalize(&src_path.join(include)) == header)
+            .then_some(include)
+    })
+}
+
+/// Returns `path` from `#include "path"`.
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?;
+    let rest = rest.trim_start().strip_prefix('"')?;
+    rest.split_once('"').map(|(path, _)| path)
+}
+
+/// Rewrites quoted includes of `old_header` in `content` to point at `new_header`.
+///
+/// `file` is where the content currently lives (used to resolve includes) and
+/// `new_location` where it will live afterwards (used to compute the new include path).
+fn rewrite_includes(
+    content: &str,
+    file: &Path,
+    new_location: &Path,
+    src_path: &Path,
+    old_header: &Path,
+    new_header: &Path,
+) -> String {
+    let Some(base) = file.parent() else {
+        return content.to_string();
+    };
+    let new_base = new_location.parent().unwrap_or(base);
+
+    let mut output = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let replacement = parse_include(line).and_then(|include| {
+            let new_include = if normalize(&base.join(include)) == old_header {
+                relative_path(new_base, new_header)
+            } else if normalize(&src_path.join(include)) == old_header {
+                relative_path(src_path, new_header)
+            } else {
+                return None;
+            };
+            Some(line.replacen(&format!("\"{}\"", include), &format!("\"{}\"", new_include), 1))
+        });
+        output.push_str(replacement.as_deref().unwrap_or(line));
+    }
+    output
+}
+
+/// Returns the `/`-separated path of `to` relative to the directory `from`.
+fn relative_path(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    std::iter::repeat_n("..".to_string(), from.len() - common)
+        .chain(to[common..].iter().map(|c| c.as_os_str().to_string_lossy().to_string()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Prints or applies `changes`, staging them in Git if requested.
+fn execute(context: &Context, changes: &[Change], options: EditOptions) -> Result<()> {
+    let project_path = &context.project_path;
+    let display = |path: &Path| path.strip_prefix(project_path).unwrap_or(path).display().to_string();
+
+    if options.dry_run {
+        println!("Dry run — no files will be changed:");
+        for change in changes {
+            match change {
+                Change::Delete(path) => println!("  delete  {}", display(path)),
+                Change::Move { from, to } => println!("  move    {} → {}", display(from), display(to)),
+                Change::Write { path, .. } => println!("  update  {}", display(path)),
+            }
+        }
+        return Ok(());
+    }
+
+    for change in changes {
+        match change {
+            Change::Delete(path) => {
+                fs::remove_file(path).with_context(|| format!("Failed to delete {}", path.display()))?;
+                println!("Deleted file: {}", path.display());
+            }
+            Change::Move { from, to } => {
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(from, to)
+                    .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+                println!("Moved file: {} → {}", from.display(), to.display());
+            }
+            Change::Write { path, content } => {
+                fs::write(path, content).with_context(|| format!("Failed to update {}", path.display()))?;
+                println!("Updated file: {}", path.display());
+            }
+        }
+    }
+
+    if options.stage {
+        stage_changes(project_path, changes)?;
+    }
+    Ok(())
+}
+
+/// Stages the applied `changes` in the project's Git index.
+fn stage_changes(project_path: &Path, changes: &[Change]) -> Result<()> {
+    let repo = Repository::open(project_path)
+        .with_context(|| format!("Cannot stage changes: no Git repository at {}", project_path.display()))?;
+    let mut index = repo.index()?;
+    let relative = |path: &Path| path.strip_prefix(project_path).map(Path::to_path_buf);
+
+    for change in changes {
+        match change {
+            Change::Delete(path) => index.remove_path(&relative(path)?)?,
+            Change::Move { from, to } => {
+                index.remove_path(&relative(from)?)?;
+                index.add_path(&relative(to)?)?;
+            }
+            Change::Write { path, .. } => index.add_path(&relative(path)?)?,
+        }
+    }
+
+    index.write()?;
+    println!("Staged changes in Git.");
+    Ok(())
+}
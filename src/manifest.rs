@@ -0,0 +1,366 @@
+// src/manifest.rs
+//! The per-project `jumake.toml` manifest.
+//!
+//! `jumake new` writes the manifest and every other command reads it through
+//! [`crate::context::Context::load`]. Projects created before the manifest existed are
+//! migrated on first use: the settings are recovered from the CMake files and the legacy
+//! `.jumake` build-type file.
+
+use crate::cmake_edit::CMakeDocument;
+use crate::error::JuMakeError;
+use anyhow::{Context as AnyhowContext, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const MANIFEST_FILE_NAME: &str = "jumake.toml";
+
+/// File that stored the last used build type before `jumake.toml` existed.
+pub const LEGACY_BUILD_TYPE_FILE: &str = ".jumake";
+
+pub const DEFAULT_VERSION: &str = "0.0.1";
+pub const DEFAULT_BUILD_TYPE: &str = "Release";
+/// Build types accepted by `jumake build -t` and the `build_type` settings.
+pub const BUILD_TYPES: &[&str] = &["Debug", "Release", "RelWithDebInfo", "MinSizeRel"];
+
+/// Contents of `jumake.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    pub project: ProjectInfo,
+    #[serde(default)]
+    pub juce: JuceSource,
+    #[serde(default)]
+    pub build: BuildSettings,
+}
+
+/// The `[project]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default = "default_version")]
+    pub version: String,
+}
+
+/// The `[juce]` table: how the project obtains JUCE.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "kebab-case")]
+pub enum JuceSource {
+    /// `modules/JUCE` is a symlink to a JUCE install from the global configuration: the one
+    /// with the pinned `version` (or install name), otherwise the default install.
+    Symlink {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+    },
+    /// `modules/JUCE` is a git submodule cloned from `url`, checked out at `rev` if given.
+    Submodule {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+    },
+    /// JUCE is downloaded at configure time with CMake's `FetchContent`: from the git
+    /// repository `url` at `rev`, or from the archive `url` verified against `hash`
+    /// (e.g. `SHA256=...`).
+    FetchContent {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hash: Option<String>,
+    },
+}
+
+impl Default for JuceSource {
+    fn default() -> Self {
+        Self::Symlink { version: None }
+    }
+}
+
+impl FromStr for JuceSource {
+    type Err = anyhow::Error;
+
+    /// Parses `symlink[:<version|install>]`, `git:<url>[@<tag|branch|commit>]` (a submodule)
+    /// or `<archive-url>#<ALGO>=<hash>` (downloaded with `FetchContent`).
+    fn from_str(spec: &str) -> Result<Self> {
+        if spec == "symlink" {
+            return Ok(Self::Symlink { version: None });
+        }
+        if let Some(version) = spec.strip_prefix("symlink:") {
+            return Ok(Self::Symlink { version: Some(version.to_string()) });
+        }
+        let Some(location) = spec.strip_prefix("git:") else {
+            if let Some((url, hash)) = spec.split_once('#') {
+                if url.contains("://") && hash.contains('=') {
+                    return Ok(Self::FetchContent { url: url.to_string(), rev: None, hash: Some(hash.to_string()) });
+                }
+            }
+            anyhow::bail!(JuMakeError::Invalid(format!(
+                "Invalid JUCE source '{}': expected 'symlink[:<version>]', 'git:<url>[@<rev>]' or '<archive-url>#<ALGO>=<hash>'",
+                spec
+            )));
+        };
+
+        // `git@host:path` URLs contain '@' too, so only a suffix without '/' or ':' is a revision
+        let (url, rev) = match location.rsplit_once('@') {
+            Some((url, rev)) if !url.is_empty() && !rev.is_empty() && !rev.contains(['/', ':']) => {
+                (url, Some(rev.to_string()))
+            }
+            _ => (location, None),
+        };
+        if url.is_empty() {
+            anyhow::bail!(JuMakeError::Invalid(format!(
+                "Invalid JUCE source '{}': missing repository URL",
+                spec
+            )));
+        }
+        Ok(Self::Submodule { url: url.to_string(), rev })
+    }
+}
+
+/// The `[build]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildSettings {
+    /// Build type used when none is given on the command line; updated by `jumake build -t`.
+    #[serde(default = "default_build_type")]
+    pub build_type: String,
+    /// CMake generator; detected at configure time if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    /// Extra arguments passed to the CMake configure step; `jumake build -D` adds to them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cmake_args: Vec<String>,
+    /// CMake toolchain file, relative to the project unless it lies outside of it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+    /// Target built instead of all targets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Number of parallel build jobs; by default all but two of the CPUs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    /// Extra arguments passed to `cmake --build`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_args: Vec<String>,
+}
+
+impl Default for BuildSettings {
+    fn default() -> Self {
+        Self {
+            build_type: default_build_type(),
+            generator: None,
+            cmake_args: Vec::new(),
+            toolchain: None,
+            target: None,
+            jobs: None,
+            build_args: Vec::new(),
+        }
+    }
+}
+
+/// Options of `jumake build` that change the `[build]` table.
+#[derive(Debug, Clone, Default)]
+pub struct BuildOverrides {
+    /// Cache variables as `KEY=VALUE`
+    pub define: Vec<String>,
+    /// Toolchain file, relative to the current directory
+    pub toolchain: Option<PathBuf>,
+    pub generator: Option<String>,
+    pub target: Option<String>,
+    pub jobs: Option<usize>,
+    /// Arguments for `cmake --build`
+    pub build_args: Vec<String>,
+    /// Drop the saved options before applying the others
+    pub reset: bool,
+}
+
+impl BuildSettings {
+    /// Applies the options given to `jumake build` in the project at `project_path`. Options
+    /// not given keep their saved values, unless `reset` is set.
+    pub fn apply(&mut self, overrides: BuildOverrides, project_path: &Path) -> Result<()> {
+        if overrides.jobs == Some(0) {
+            anyhow::bail!(JuMakeError::Invalid("--jobs must be at least 1".into()));
+        }
+        let toolchain = match &overrides.toolchain {
+            Some(toolchain) => {
+                let path = toolchain.canonicalize().map_err(|_| {
+                    JuMakeError::Invalid(format!("Toolchain file not found: {}", toolchain.display()))
+                })?;
+                // Kept relative to the project, so that it still works when the project is moved
+                let project_path = project_path.canonicalize()?;
+                let path = path.strip_prefix(&project_path).unwrap_or(&path);
+                Some(path.display().to_string())
+            }
+            None => None,
+        };
+
+        if overrides.reset {
+            *self = Self { build_type: std::mem::take(&mut self.build_type), ..Self::default() };
+        }
+        for definition in &overrides.define {
+            self.define(definition)?;
+        }
+        self.toolchain = toolchain.or(self.toolchain.take());
+        self.generator = overrides.generator.or(self.generator.take());
+        self.target = overrides.target.or(self.target.take());
+        self.jobs = overrides.jobs.or(self.jobs);
+        if !overrides.build_args.is_empty() {
+            self.build_args = overrides.build_args;
+        }
+        Ok(())
+    }
+
+    /// The `jumake build` options for the target, jobs and `cmake --build` arguments in effect.
+    pub fn build_step_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(target) = &self.target {
+            args.push(format!("--target {}", target));
+        }
+        if let Some(jobs) = self.jobs {
+            args.push(format!("--jobs {}", jobs));
+        }
+        if !self.build_args.is_empty() {
+            args.push(format!("-- {}", self.build_args.join(" ")));
+        }
+        args
+    }
+
+    /// Adds a `KEY=VALUE` (or `KEY:TYPE=VALUE`) cache variable to `cmake_args`, replacing
+    /// earlier definitions of `KEY`.
+    pub fn define(&mut self, definition: &str) -> Result<()> {
+        let Some((key, _)) = definition.split_once('=') else {
+            anyhow::bail!(JuMakeError::Invalid(format!(
+                "Invalid cache variable '{}': expected KEY=VALUE",
+                definition
+            )));
+        };
+        let key = key.split_once(':').map_or(key, |(key, _)| key);
+        if key.is_empty() {
+            anyhow::bail!(JuMakeError::Invalid(format!(
+                "Invalid cache variable '{}': the name is missing",
+                definition
+            )));
+        }
+
+        let defined_key = |definition: &str| {
+            let name = definition.split_once('=').map_or(definition, |(name, _)| name);
+            name.split_once(':').map_or(name, |(name, _)| name).to_string()
+        };
+        let mut args = Vec::new();
+        let mut old_args = std::mem::take(&mut self.cmake_args).into_iter();
+        while let Some(arg) = old_args.next() {
+            match arg.strip_prefix("-D") {
+                // `-D KEY=VALUE` as two arguments
+                Some("") => match old_args.next() {
+                    Some(next) if defined_key(&next) == key => {}
+                    Some(next) => args.extend([arg, next]),
+                    None => args.push(arg),
+                },
+                Some(definition) if defined_key(definition) == key => {}
+                _ => args.push(arg),
+            }
+        }
+        args.push(format!("-D{}", definition));
+        self.cmake_args = args;
+        Ok(())
+    }
+}
+
+fn default_version() -> String {
+    DEFAULT_VERSION.to_string()
+}
+
+fn default_build_type() -> String {
+    DEFAULT_BUILD_TYPE.to_string()
+}
+
+impl ProjectManifest {
+    /// Manifest for a new project with default settings.
+    pub fn new(name: &str, template: Option<String>) -> Self {
+        Self {
+            project: ProjectInfo { name: name.to_string(), template, version: default_version() },
+            juce: JuceSource::default(),
+            build: BuildSettings::default(),
+        }
+    }
+
+    /// Reads `jumake.toml` from `project_path`.
+    pub fn read(project_path: &Path) -> Result<Self> {
+        let path = project_path.join(MANIFEST_FILE_NAME);
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Writes `jumake.toml` to `project_path`.
+    pub fn write(&self, project_path: &Path) -> Result<()> {
+        let path = project_path.join(MANIFEST_FILE_NAME);
+        let content = toml::to_string(self).context("Failed to serialize the project manifest")?;
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Reads `jumake.toml`, migrating a project without one first.
+    pub fn load(project_path: &Path) -> Result<Self> {
+        if project_path.join(MANIFEST_FILE_NAME).is_file() {
+            return Self::read(project_path);
+        }
+
+        let manifest = Self::migrate(project_path)?;
+        manifest.write(project_path)?;
+        let legacy = project_path.join(LEGACY_BUILD_TYPE_FILE);
+        if legacy.is_file() {
+            fs::remove_file(&legacy).with_context(|| format!("Failed to remove {}", legacy.display()))?;
+        }
+        println!("Migrated project settings to {}", MANIFEST_FILE_NAME);
+        Ok(manifest)
+    }
+
+    /// Recovers the settings of a project created before `jumake.toml` existed, recognised by
+    /// its `.jumake` file or the `JUMAKE_TEMPLATE` marker in `src/CMakeLists.txt`.
+    fn migrate(project_path: &Path) -> Result<Self> {
+        let legacy_build_type = fs::read_to_string(project_path.join(LEGACY_BUILD_TYPE_FILE)).ok();
+        let template = read_template_name(project_path);
+        if legacy_build_type.is_none() && template.is_none() {
+            anyhow::bail!("{} is not a JuMake project: {} not found", project_path.display(), MANIFEST_FILE_NAME);
+        }
+        let root_cmakelists = project_path.join("CMakeLists.txt");
+        let content = fs::read_to_string(&root_cmakelists)
+            .with_context(|| format!("Failed to read {}", root_cmakelists.display()))?;
+        let document = CMakeDocument::parse(&content)
+            .with_context(|| format!("Failed to parse {}", root_cmakelists.display()))?;
+        let project_arguments = document
+            .find_command("project", None)
+            .map(|id| document.command_arguments(id))
+            .unwrap_or_default();
+        let name = project_arguments
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Project name not found in {}", root_cmakelists.display()))?;
+
+        let mut manifest = Self::new(&name, template);
+        if let Some(version) = project_arguments
+            .iter()
+            .position(|argument| argument == "VERSION")
+            .and_then(|index| project_arguments.get(index + 1))
+        {
+            manifest.project.version = version.clone();
+        }
+        match legacy_build_type.as_deref().map(str::trim) {
+            Some(build_type) if BUILD_TYPES.contains(&build_type) => manifest.build.build_type = build_type.to_string(),
+            Some("") | None => {}
+            Some(build_type) => eprintln!(
+                "⚠️  Ignoring invalid build type '{}' in {}; using {}.",
+                build_type, LEGACY_BUILD_TYPE_FILE, DEFAULT_BUILD_TYPE
+            ),
+        }
+        Ok(manifest)
+    }
+}
+
+/// Reads `set(JUMAKE_TEMPLATE "...")` from `src/CMakeLists.txt`.
+fn read_template_name(project_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(project_path.join("src").join("CMakeLists.txt")).ok()?;
+    let document = CMakeDocument::parse(&content).ok()?;
+    let id = document.find_command("set", Some("JUMAKE_TEMPLATE"))?;
+    document.command_arguments(id).get(1).cloned()
+}
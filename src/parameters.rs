@@ -0,0 +1,237 @@
+// src/parameters.rs
+//! Adds `juce::AudioProcessorValueTreeState` parameters to AudioPlugin projects.
+//!
+//! The AudioPlugin template contains marker regions (see [`crate::regions`]) in
+//! `ParameterIDs.h`, `PluginProcessor.cpp` and the editor files; `jumake add param`
+//! appends generated code to those regions.
+
+use crate::context::Context;
+use crate::error::JuMakeError;
+use crate::placeholders::is_identifier;
+use crate::regions::{append_to_region, region_body};
+use anyhow::{Context as AnyhowContext, Result};
+use std::fs;
+use std::path::PathBuf;
+
+/// A float parameter to add to the plugin.
+#[derive(Debug, Clone)]
+pub struct ParameterSpec {
+    /// Parameter ID; also used as the C++ identifier of the ID constant.
+    pub id: String,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub unit: Option<String>,
+    /// Whether to add a slider with an attachment to the plugin editor.
+    pub with_slider: bool,
+}
+
+impl ParameterSpec {
+    /// Builds a spec from command-line values, validating the ID and the range.
+    ///
+    /// `range` has the form `min..max` and defaults to `0..1`; `default` defaults to `min`.
+    pub fn new(
+        id: &str,
+        range: Option<&str>,
+        default: Option<f32>,
+        unit: Option<String>,
+        with_slider: bool,
+    ) -> Result<Self> {
+        if !is_identifier(id) {
+            anyhow::bail!(JuMakeError::Invalid(format!(
+                "Parameter ID '{}' must be a valid C++ identifier",
+                id
+            )));
+        }
+
+        let (min, max) = match range {
+            Some(range) => parse_range(range)?,
+            None => (0.0, 1.0),
+        };
+        let default = default.unwrap_or(min);
+        if !default.is_finite() {
+            anyhow::bail!(JuMakeError::Invalid(format!("Default value {} must be a finite number", default)));
+        }
+        if !(min..=max).contains(&default) {
+            anyhow::bail!(JuMakeError::Invalid(format!(
+                "Default value {} is outside the range {}..{}",
+                default,
+                min,
+                max
+            )));
+        }
+
+        Ok(Self { id: id.to_string(), min, max, default, unit, with_slider })
+    }
+}
+
+/// Parses a `min..max` range.
+fn parse_range(range: &str) -> Result<(f32, f32)> {
+    let (min, max) = range
+        .split_once("..")
+        .ok_or_else(|| JuMakeError::Invalid(format!("Invalid range '{}', expected 'min..max'", range)))?;
+    let min: f32 =
+        min.trim().parse().map_err(|_| JuMakeError::Invalid(format!("Invalid range minimum '{}'", min)))?;
+    let max: f32 =
+        max.trim().parse().map_err(|_| JuMakeError::Invalid(format!("Invalid range maximum '{}'", max)))?;
+    if !min.is_finite() || !max.is_finite() {
+        anyhow::bail!(JuMakeError::Invalid(format!(
+            "Invalid range '{}': minimum and maximum must be finite numbers",
+            range
+        )));
+    }
+    if min >= max {
+        anyhow::bail!(JuMakeError::Invalid(format!(
+            "Invalid range '{}': minimum must be less than maximum",
+            range
+        )));
+    }
+    Ok((min, max))
+}
+
+/// Adds a parameter to the AudioPlugin project in `context`.
+///
+/// All files are edited in memory first and only written once every edit succeeded.
+pub fn add_parameter(context: &Context, spec: &ParameterSpec) -> Result<()> {
+    let src_path = context.project_path.join("src");
+    let id = &spec.id;
+
+    let mut edits = vec![
+        RegionEdit::new(src_path.join("ParameterIDs.h"), "parameter-ids", id_declaration(spec)),
+        RegionEdit::new(src_path.join("PluginProcessor.cpp"), "parameters", layout_entry(spec)),
+    ];
+    if spec.with_slider {
+        let slider = format!("{}Slider", id);
+        let attachment = format!("{}Attachment", id);
+        edits.extend([
+            RegionEdit::new(
+                src_path.join("PluginEditor.h"),
+                "parameter-sliders",
+                format!("juce::Slider {};", slider),
+            ),
+            RegionEdit::new(
+                src_path.join("PluginEditor.h"),
+                "parameter-attachments",
+                format!("std::unique_ptr<juce::AudioProcessorValueTreeState::SliderAttachment> {};", attachment),
+            ),
+            RegionEdit::new(
+                src_path.join("PluginEditor.cpp"),
+                "parameter-controls",
+                format!(
+                    "{slider}.setSliderStyle (juce::Slider::RotaryHorizontalVerticalDrag);\n\
+                     {slider}.setTextBoxStyle (juce::Slider::TextBoxBelow, false, 80, 20);\n\
+                     addAndMakeVisible ({slider});\n\
+                     {attachment} = std::make_unique<juce::AudioProcessorValueTreeState::SliderAttachment> (\n    \
+                     processorRef.parameters, ParameterIDs::{id}.getParamID(), {slider});",
+                ),
+            ),
+            RegionEdit::new(
+                src_path.join("PluginEditor.cpp"),
+                "parameter-layout",
+                format!("{}.setBounds (area.removeFromLeft (100));", slider),
+            ),
+        ]);
+    }
+
+    // Refuse duplicates before touching anything
+    let ids_path = src_path.join("ParameterIDs.h");
+    let ids = fs::read_to_string(&ids_path)
+        .with_context(|| format!("Failed to read {} — is this an AudioPlugin project?", ids_path.display()))?;
+    let existing = region_body(&ids, "parameter-ids")
+        .with_context(|| format!("Cannot add parameters to {}", ids_path.display()))?;
+    if existing.contains(&format!(" {} ", id)) {
+        anyhow::bail!(JuMakeError::Invalid(format!("Parameter '{}' already exists", id)));
+    }
+
+    // Apply all edits in memory, grouping them by file
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+    for edit in edits {
+        let index = match files.iter().position(|(path, _)| *path == edit.path) {
+            Some(index) => index,
+            None => {
+                let content = fs::read_to_string(&edit.path)
+                    .with_context(|| format!("Failed to read {}", edit.path.display()))?;
+                files.push((edit.path.clone(), content));
+                files.len() - 1
+            }
+        };
+        let content = &mut files[index].1;
+        *content = append_to_region(content, edit.region, &edit.lines)
+            .with_context(|| format!("Cannot edit {}", edit.path.display()))?;
+    }
+
+    for (path, content) in files {
+        fs::write(&path, content).with_context(|| format!("Failed to update {}", path.display()))?;
+        println!("Updated file: {}", path.display());
+    }
+
+    println!("Parameter '{}' added successfully!", id);
+    Ok(())
+}
+
+/// Code to append to one marker region of one file.
+struct RegionEdit {
+    path: PathBuf,
+    region: &'static str,
+    lines: String,
+}
+
+impl RegionEdit {
+    fn new(path: PathBuf, region: &'static str, lines: String) -> Self {
+        Self { path, region, lines }
+    }
+}
+
+fn id_declaration(spec: &ParameterSpec) -> String {
+    format!("inline const juce::ParameterID {} {{ \"{}\", 1 }};", spec.id, spec.id)
+}
+
+fn layout_entry(spec: &ParameterSpec) -> String {
+    let mut arguments = vec![
+        format!("ParameterIDs::{}", spec.id),
+        format!("\"{}\"", display_name(&spec.id)),
+        format!(
+            "juce::NormalisableRange<float> ({}, {})",
+            float_literal(spec.min),
+            float_literal(spec.max)
+        ),
+        float_literal(spec.default),
+    ];
+    if let Some(unit) = &spec.unit {
+        arguments.push(format!(
+            "juce::AudioParameterFloatAttributes().withLabel (\"{}\")",
+            unit.replace('\\', "\\\\").replace('"', "\\\"")
+        ));
+    }
+    format!(
+        "layout.add (std::make_unique<juce::AudioParameterFloat> (\n    {}));",
+        arguments.join(",\n    ")
+    )
+}
+
+/// Turns `cutoff_freq` or `cutoffFreq` into `Cutoff Freq`.
+fn display_name(id: &str) -> String {
+    let mut name = String::new();
+    let mut previous: Option<char> = None;
+    for c in id.chars() {
+        if c == '_' {
+            if !name.is_empty() && !name.ends_with(' ') {
+                name.push(' ');
+            }
+        } else if name.is_empty() || name.ends_with(' ') {
+            name.push(c.to_ascii_uppercase());
+        } else {
+            if c.is_ascii_uppercase() && previous.is_some_and(|p| p.is_ascii_lowercase()) {
+                name.push(' ');
+            }
+            name.push(c);
+        }
+        previous = Some(c);
+    }
+    name.trim_end().to_string()
+}
+
+/// Formats a value as a C++ float literal, e.g. `-60.0f`.
+fn float_literal(value: f32) -> String {
+    format!("{:?}f", value)
+}
@@ -0,0 +1,298 @@
+// src/placeholders.rs
+//! Placeholder engine used by project templates and `jumake add` templates.
+//!
+//! Supported syntax:
+//! - `{{name}}` is replaced by the value of the variable `name`.
+//! - `{{#if name}} ... {{else}} ... {{/if}}` keeps the first branch if `name` is non-empty.
+//! - `\{{` produces a literal `{{`.
+//!
+//! Anything between `{{` and `}}` that isn't one of the forms above (for example C++
+//! brace initialisers like `{{1, 2}}`) is left untouched. Block tags that stand alone on
+//! their line are removed together with that line.
+
+use crate::config::load_config;
+use crate::initialize_git::author_identity;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Manufacturer code of plugins when none is configured.
+const DEFAULT_MANUFACTURER_CODE: &str = "Juce";
+
+/// Variables available to a template, by name.
+pub type Variables = BTreeMap<String, String>;
+
+/// Errors raised while rendering a template.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PlaceholderError {
+    #[error("Unknown template variable '{name}' at line {line}. Available variables: {available}")]
+    UnknownVariable {
+        name: String,
+        line: usize,
+        available: String,
+    },
+    #[error("Unexpected '{{{{{tag}}}}}' at line {line}")]
+    UnexpectedTag { tag: String, line: usize },
+    #[error("Unterminated '{{{{#if {name}}}}}' opened at line {line}")]
+    UnterminatedBlock { name: String, line: usize },
+}
+
+/// Builds the standard variables shared by all templates.
+///
+/// - `project_name`: the project name as given on the command line
+/// - `class_name`: a C++ identifier derived from the project name
+/// - `plugin_code`: a four-character plugin code derived from the project name
+/// - `namespace`: empty unless set by the caller
+/// - `year`: the current year
+/// - `author`: `user.name` from the global Git configuration, otherwise `author_name` from the
+///   JuMake configuration, if any
+/// - `company_name`: `company_name` from the JuMake configuration, if any
+/// - `manufacturer_code`: `manufacturer_code` from the JuMake configuration, or `Juce`
+pub fn standard_variables(project_name: &str) -> Variables {
+    let config = load_config().unwrap_or_default();
+    let class_name = to_class_name(project_name);
+    let mut variables = Variables::new();
+    variables.insert("project_name".into(), project_name.to_string());
+    variables.insert("plugin_code".into(), plugin_code(&class_name));
+    variables.insert("class_name".into(), class_name);
+    variables.insert("namespace".into(), String::new());
+    variables.insert("year".into(), current_year().to_string());
+    variables.insert("author".into(), author_identity(None).0.unwrap_or_default());
+    variables.insert("company_name".into(), config.company_name.unwrap_or_default());
+    variables.insert(
+        "manufacturer_code".into(),
+        config.manufacturer_code.unwrap_or_else(|| DEFAULT_MANUFACTURER_CODE.to_string()),
+    );
+    variables
+}
+
+/// Renders `text`, substituting placeholders and evaluating conditionals.
+pub fn render(text: &str, variables: &Variables) -> Result<String, PlaceholderError> {
+    let tokens = tokenize(text);
+
+    let lookup = |name: &str, line: usize| {
+        variables
+            .get(name)
+            .ok_or_else(|| PlaceholderError::UnknownVariable {
+                name: name.to_string(),
+                line,
+                available: variables.keys().cloned().collect::<Vec<_>>().join(", "),
+            })
+    };
+
+    let mut output = String::with_capacity(text.len());
+    // One entry per open `#if`: (variable name, opening line, condition, inside else branch)
+    let mut blocks: Vec<(String, usize, bool, bool)> = Vec::new();
+
+    for token in tokens {
+        let active = blocks
+            .iter()
+            .all(|(_, _, condition, in_else)| *condition != *in_else);
+
+        match token {
+            Token::Text(text) => {
+                if active {
+                    output.push_str(text);
+                }
+            }
+            Token::Variable { name, line } => {
+                let value = lookup(name, line)?;
+                if active {
+                    output.push_str(value);
+                }
+            }
+            Token::If { name, line } => {
+                let condition = !lookup(name, line)?.is_empty();
+                blocks.push((name.to_string(), line, condition, false));
+            }
+            Token::Else { line } => match blocks.last_mut() {
+                Some((_, _, _, in_else @ false)) => *in_else = true,
+                _ => {
+                    return Err(PlaceholderError::UnexpectedTag {
+                        tag: "else".into(),
+                        line,
+                    })
+                }
+            },
+            Token::EndIf { line } => {
+                if blocks.pop().is_none() {
+                    return Err(PlaceholderError::UnexpectedTag {
+                        tag: "/if".into(),
+                        line,
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some((name, line, _, _)) = blocks.pop() {
+        return Err(PlaceholderError::UnterminatedBlock { name, line });
+    }
+
+    Ok(output)
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+    Text(&'a str),
+    Variable { name: &'a str, line: usize },
+    If { name: &'a str, line: usize },
+    Else { line: usize },
+    EndIf { line: usize },
+}
+
+impl Token<'_> {
+    fn is_block(&self) -> bool {
+        matches!(self, Token::If { .. } | Token::Else { .. } | Token::EndIf { .. })
+    }
+}
+
+/// Splits a template into text and tag tokens.
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    let mut line = 1;
+
+    while let Some(start) = rest.find("{{") {
+        // `\{{` escapes a literal `{{`
+        if start > 0 && rest.as_bytes()[start - 1] == b'\\' {
+            push_text(&mut tokens, &rest[..start - 1], &mut line);
+            push_text(&mut tokens, "{{", &mut line);
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let Some(end) = rest[start + 2..].find("}}").map(|e| start + 2 + e) else {
+            break;
+        };
+        let Some(tag) = parse_tag(rest[start + 2..end].trim(), line + rest[..start].matches('\n').count()) else {
+            // Not a placeholder: keep the opening braces and continue after them
+            push_text(&mut tokens, &rest[..start + 2], &mut line);
+            rest = &rest[start + 2..];
+            continue;
+        };
+
+        let mut before = &rest[..start];
+        let mut after = &rest[end + 2..];
+
+        // Standalone block tags swallow their whole line
+        if tag.is_block() {
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let at_line_start = (line_start > 0 || ends_with_newline(&tokens))
+                && before[line_start..].trim_matches([' ', '\t']).is_empty();
+            let line_end = after.find('\n');
+            let at_line_end = after[..line_end.unwrap_or(after.len())]
+                .trim_matches([' ', '\t', '\r'])
+                .is_empty();
+            if at_line_start && at_line_end {
+                before = &before[..line_start];
+                after = &after[line_end.map_or(after.len(), |i| i + 1)..];
+                line += 1;
+            }
+        }
+
+        push_text(&mut tokens, before, &mut line);
+        tokens.push(tag);
+        rest = after;
+    }
+
+    push_text(&mut tokens, rest, &mut line);
+    tokens
+}
+
+fn push_text<'a>(tokens: &mut Vec<Token<'a>>, text: &'a str, line: &mut usize) {
+    if !text.is_empty() {
+        *line += text.matches('\n').count();
+        tokens.push(Token::Text(text));
+    }
+}
+
+fn ends_with_newline(tokens: &[Token<'_>]) -> bool {
+    match tokens.iter().rev().find(|t| !t.is_block()) {
+        Some(Token::Text(text)) => text.ends_with('\n'),
+        Some(_) => false,
+        None => true,
+    }
+}
+
+/// Parses the inside of a `{{ ... }}` tag, returning `None` if it isn't a placeholder.
+fn parse_tag(inner: &str, line: usize) -> Option<Token<'_>> {
+    if let Some(name) = inner.strip_prefix("#if") {
+        let valid = name.starts_with(char::is_whitespace) && is_identifier(name.trim());
+        return valid.then(|| Token::If { name: name.trim(), line });
+    }
+    match inner {
+        "else" => Some(Token::Else { line }),
+        "/if" => Some(Token::EndIf { line }),
+        name if is_identifier(name) => Some(Token::Variable { name, line }),
+        _ => None,
+    }
+}
+
+/// Returns true if `name` is a valid C++ identifier.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Converts an arbitrary project name into a PascalCase C++ identifier.
+pub fn to_class_name(name: &str) -> String {
+    let class_name: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    match class_name.chars().next() {
+        None => "Project".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", class_name),
+        Some(_) => class_name,
+    }
+}
+
+/// Derives a four-character plugin code with exactly one (leading) upper-case letter,
+/// as required by GarageBand.
+fn plugin_code(class_name: &str) -> String {
+    let chars: Vec<char> = class_name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .skip_while(|c| !c.is_ascii_alphabetic())
+        .collect();
+
+    let mut code = match chars.split_first() {
+        Some((first, rest)) => std::iter::once(first.to_ascii_uppercase())
+            .chain(rest.iter().map(char::to_ascii_lowercase))
+            .take(4)
+            .collect::<String>(),
+        None => "Jmk".to_string(),
+    };
+    while code.len() < 4 {
+        code.push('0');
+    }
+    code
+}
+
+/// Returns the current (UTC) year.
+fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    yoe + era * 400 + i64::from(month <= 2)
+}
@@ -0,0 +1,325 @@
+// src/presets.rs
+//! `CMakePresets.json` for JuMake projects, and the presets `jumake build --preset` builds with.
+//!
+//! The generated file has a configure and a build preset per build type with the cache
+//! variables of JuMake's own configure step, so IDEs and plain `cmake --preset` builds match
+//! `jumake build`. Presets are looked up in `CMakePresets.json` and `CMakeUserPresets.json`.
+
+use crate::build::{is_multi_config, BUILD_DIR, JUMAKE_CACHE_VARIABLES};
+use crate::context::Context;
+use crate::create_files::CMAKE_MINIMUM_VERSION;
+use crate::manifest::{ProjectManifest, BUILD_TYPES};
+use anyhow::{Context as AnyhowContext, Result};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PRESETS_FILE: &str = "CMakePresets.json";
+pub const USER_PRESETS_FILE: &str = "CMakeUserPresets.json";
+
+/// Hidden configure preset holding the settings shared by all build types.
+const BASE_PRESET: &str = "jumake-base";
+
+lazy_static! {
+    static ref MACRO_RE: Regex = Regex::new(r"\$(\w*)\{([^}]*)\}").unwrap();
+}
+
+/// A preset selected with `jumake build --preset`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    /// Configure preset for `cmake --preset`
+    pub configure_preset: String,
+    /// Build preset for `cmake --build --preset`, if there is one of the given name
+    pub build_preset: Option<String>,
+    /// Build directory of the configure preset, with macros expanded
+    pub binary_dir: PathBuf,
+    /// Generator of the configure preset; CMake's default (or JuMake's choice) if not set
+    pub generator: Option<String>,
+    /// `CMAKE_BUILD_TYPE` of the configure preset, or the configuration of the build preset
+    pub build_type: Option<String>,
+}
+
+/// The contents of `CMakePresets.json` for the project.
+///
+/// `-D` options in the manifest's `cmake_args` become cache variables and its toolchain the
+/// `toolchainFile`; other arguments can't be expressed in presets and are returned separately.
+pub fn generate_presets(manifest: &ProjectManifest) -> (String, Vec<String>) {
+    let (variables, other_args) = cache_variables(&manifest.build.cmake_args);
+
+    // Like `jumake build`: one build directory, or one per build type
+    let multi_config = manifest.build.generator.as_deref().is_some_and(is_multi_config);
+    let binary_dir = |build_type: &str| {
+        let build_type_dir = if multi_config { String::new() } else { format!("/{}", build_type) };
+        format!("${{sourceDir}}/{}{}", BUILD_DIR, build_type_dir)
+    };
+
+    let cache_variables: Map<String, Value> = JUMAKE_CACHE_VARIABLES
+        .iter()
+        .map(|(key, value)| (key.to_string(), json!(value)))
+        .chain(variables.into_iter().map(|(key, value)| (key, json!(value))))
+        .collect();
+    let mut base = json!({ "name": BASE_PRESET, "hidden": true });
+    if let Some(generator) = &manifest.build.generator {
+        base["generator"] = json!(generator);
+    }
+    if let Some(toolchain) = &manifest.build.toolchain {
+        let in_project = Path::new(toolchain).is_relative();
+        let path = if in_project { format!("${{sourceDir}}/{}", toolchain) } else { toolchain.clone() };
+        base["toolchainFile"] = json!(path);
+    }
+    base["cacheVariables"] = Value::Object(cache_variables);
+
+    let mut configure_presets = vec![base];
+    let mut build_presets = Vec::new();
+    for build_type in BUILD_TYPES {
+        let name = preset_name(build_type);
+        configure_presets.push(json!({
+            "name": name,
+            "displayName": build_type,
+            "inherits": BASE_PRESET,
+            "binaryDir": binary_dir(build_type),
+            "cacheVariables": { "CMAKE_BUILD_TYPE": build_type },
+        }));
+        build_presets.push(json!({
+            "name": name,
+            "displayName": build_type,
+            "configurePreset": name,
+            "configuration": build_type,
+        }));
+    }
+
+    let version_part = |index: usize| -> u32 {
+        CMAKE_MINIMUM_VERSION.split('.').nth(index).and_then(|part| part.parse().ok()).unwrap_or(0)
+    };
+    let presets = json!({
+        "version": 3,
+        "cmakeMinimumRequired": { "major": version_part(0), "minor": version_part(1), "patch": version_part(2) },
+        "configurePresets": configure_presets,
+        "buildPresets": build_presets,
+    });
+    let presets = serde_json::to_string_pretty(&presets).expect("JSON values always serialize");
+    (presets + "\n", other_args)
+}
+
+/// Writes `CMakePresets.json` into the project, warning about `cmake_args` left out of it.
+pub fn write_presets(context: &Context) -> Result<()> {
+    let (presets, other_args) = generate_presets(&context.manifest);
+    let path = context.project_path.join(PRESETS_FILE);
+    fs::write(&path, presets).with_context(|| format!("Failed to write {}", path.display()))?;
+    if !other_args.is_empty() {
+        eprintln!("⚠️  cmake_args not expressible in {}: {}", PRESETS_FILE, other_args.join(" "));
+    }
+    Ok(())
+}
+
+/// Name of the generated presets for `build_type`, e.g. `relwithdebinfo`.
+pub fn preset_name(build_type: &str) -> String {
+    build_type.to_lowercase()
+}
+
+/// Splits CMake arguments into `-D` cache variables and everything else.
+pub fn cache_variables(args: &[String]) -> (Vec<(String, String)>, Vec<String>) {
+    let mut variables = Vec::new();
+    let mut other_args = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let definition = match arg.strip_prefix("-D") {
+            Some("") => args.next().map(String::as_str),
+            Some(definition) => Some(definition),
+            None => None,
+        };
+        match definition.and_then(|definition| definition.split_once('=')) {
+            // `KEY:TYPE=VALUE`
+            Some((key, value)) => {
+                let key = key.split_once(':').map_or(key, |(key, _)| key);
+                variables.push((key.to_string(), value.to_string()));
+            }
+            None => other_args.push(arg.clone()),
+        }
+    }
+    (variables, other_args)
+}
+
+/// Finds the preset `name` in the project's `CMakePresets.json` and `CMakeUserPresets.json`.
+///
+/// A build preset of that name is used with its configure preset; otherwise a configure preset
+/// of that name is built in its binary directory.
+pub fn find_preset(project_path: &Path, name: &str) -> Result<Preset> {
+    let presets = Presets::load(project_path)?;
+
+    let (configure_preset, build_preset) = match presets.find("buildPresets", name) {
+        Some(_) => {
+            let configure = presets
+                .build_field(name, "configurePreset")
+                .and_then(|value| value.as_str().map(str::to_string))
+                .with_context(|| format!("Build preset '{}' has no configurePreset", name))?;
+            (configure, Some(name.to_string()))
+        }
+        None if presets.find("configurePresets", name).is_some() => (name.to_string(), None),
+        None => {
+            let names = presets.visible_names();
+            anyhow::bail!(
+                "No CMake preset named '{}'. Available presets: {}",
+                name,
+                if names.is_empty() { String::from("none") } else { names.join(", ") }
+            );
+        }
+    };
+    // `hidden` is the one field that isn't inherited
+    match presets.find("configurePresets", &configure_preset) {
+        None => anyhow::bail!("No configure preset named '{}'", configure_preset),
+        Some(preset) if preset.get("hidden").and_then(Value::as_bool) == Some(true) => {
+            anyhow::bail!("Configure preset '{}' is hidden and cannot be used", configure_preset)
+        }
+        Some(_) => {}
+    }
+
+    let binary_dir = presets
+        .configure_field(&configure_preset, "binaryDir")
+        .and_then(Value::as_str)
+        .with_context(|| format!("Configure preset '{}' has no binaryDir", configure_preset))?;
+    let binary_dir = project_path.join(expand_macros(binary_dir, project_path, &configure_preset));
+    let generator = presets.configure_field(&configure_preset, "generator").and_then(Value::as_str);
+    let build_type = presets
+        .cache_variable(&configure_preset, "CMAKE_BUILD_TYPE")
+        .or_else(|| build_preset.as_ref().and_then(|name| presets.build_field(name, "configuration")))
+        .and_then(|value| value.as_str().or_else(|| value.get("value").and_then(Value::as_str)));
+
+    Ok(Preset {
+        generator: generator.map(str::to_string),
+        build_type: build_type.map(str::to_string),
+        configure_preset,
+        build_preset,
+        binary_dir,
+    })
+}
+
+/// The presets of both files.
+struct Presets {
+    documents: Vec<Value>,
+}
+
+/// Limit on `inherits` chains, against cycles.
+const MAX_INHERITANCE_DEPTH: usize = 16;
+
+impl Presets {
+    fn load(project_path: &Path) -> Result<Self> {
+        let mut documents = Vec::new();
+        for file in [PRESETS_FILE, USER_PRESETS_FILE] {
+            let path = project_path.join(file);
+            if !path.exists() {
+                continue;
+            }
+            let text = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let document: Value =
+                serde_json::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+            documents.push(document);
+        }
+        if documents.is_empty() {
+            anyhow::bail!(
+                "No {} or {} in the project; create one with `jumake presets`",
+                PRESETS_FILE,
+                USER_PRESETS_FILE
+            );
+        }
+        Ok(Self { documents })
+    }
+
+    /// The preset `name` of the list `kind` (`configurePresets` or `buildPresets`).
+    fn find(&self, kind: &str, name: &str) -> Option<&Value> {
+        self.documents
+            .iter()
+            .filter_map(|document| document.get(kind).and_then(Value::as_array))
+            .flatten()
+            .find(|preset| preset.get("name").and_then(Value::as_str) == Some(name))
+    }
+
+    /// Names of the presets that can be selected.
+    fn visible_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for kind in ["buildPresets", "configurePresets"] {
+            for document in &self.documents {
+                for preset in document.get(kind).and_then(Value::as_array).into_iter().flatten() {
+                    let name = preset.get("name").and_then(Value::as_str).unwrap_or_default();
+                    let hidden = preset.get("hidden").and_then(Value::as_bool).unwrap_or(false);
+                    if !hidden && !name.is_empty() && !names.iter().any(|known| known == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// `field` of a preset or, if it doesn't set it, of the presets it inherits from.
+    fn inherited_field<'a>(
+        &'a self,
+        kind: &str,
+        name: &str,
+        lookup: &dyn Fn(&'a Value) -> Option<&'a Value>,
+        depth: usize,
+    ) -> Option<&'a Value> {
+        if depth > MAX_INHERITANCE_DEPTH {
+            return None;
+        }
+        let preset = self.find(kind, name)?;
+        if let Some(value) = lookup(preset) {
+            return Some(value);
+        }
+        let parents: Vec<&str> = match preset.get("inherits") {
+            Some(Value::String(parent)) => vec![parent.as_str()],
+            Some(Value::Array(parents)) => parents.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        parents.into_iter().find_map(|parent| self.inherited_field(kind, parent, lookup, depth + 1))
+    }
+
+    fn configure_field(&self, name: &str, field: &str) -> Option<&Value> {
+        self.inherited_field("configurePresets", name, &|preset| preset.get(field), 0)
+    }
+
+    fn build_field(&self, name: &str, field: &str) -> Option<&Value> {
+        self.inherited_field("buildPresets", name, &|preset| preset.get(field), 0)
+    }
+
+    fn cache_variable(&self, name: &str, variable: &str) -> Option<&Value> {
+        self.inherited_field(
+            "configurePresets",
+            name,
+            &|preset| preset.get("cacheVariables").and_then(|variables| variables.get(variable)),
+            0,
+        )
+    }
+}
+
+/// Expands the macros of a preset path that JuMake can know before configuring.
+fn expand_macros(value: &str, source_dir: &Path, preset_name: &str) -> String {
+    MACRO_RE
+        .replace_all(value, |captures: &Captures| {
+            let expanded = match (&captures[1], &captures[2]) {
+                ("", "sourceDir") => Some(source_dir.display().to_string()),
+                ("", "sourceParentDir") => source_dir.parent().map(|parent| parent.display().to_string()),
+                ("", "sourceDirName") => source_dir.file_name().map(|name| name.to_string_lossy().into_owned()),
+                ("", "presetName") => Some(preset_name.to_string()),
+                ("", "hostSystemName") => Some(host_system_name().to_string()),
+                ("", "dollar") => Some(String::from("$")),
+                ("env" | "penv", name) => Some(std::env::var(name).unwrap_or_default()),
+                _ => None,
+            };
+            expanded.unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+/// The value of CMake's `${hostSystemName}`.
+fn host_system_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "Windows"
+    } else if cfg!(target_os = "macos") {
+        "Darwin"
+    } else {
+        "Linux"
+    }
+}
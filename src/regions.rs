@@ -0,0 +1,87 @@
+// src/regions.rs
+//! Marker-based editing of generated source files.
+//!
+//! Templates mark regions that JuMake may edit later with a pair of comments:
+//!
+//! ```cpp
+//! // jumake:begin parameters
+//! // jumake:end parameters
+//! ```
+//!
+//! Everything outside the markers is left untouched, so user edits survive.
+
+use anyhow::Result;
+
+const BEGIN_MARKER: &str = "// jumake:begin";
+const END_MARKER: &str = "// jumake:end";
+
+/// Location of a region's body within a file.
+struct RegionBounds {
+    /// Start of the line following the begin marker.
+    body_start: usize,
+    /// Start of the line containing the end marker.
+    body_end: usize,
+    /// Indentation of the end marker line.
+    indent: String,
+}
+
+/// Finds the region called `name` in `content`.
+fn find_region(content: &str, name: &str) -> Result<RegionBounds> {
+    let mut body_start = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some(marker) = trimmed.strip_prefix(BEGIN_MARKER) {
+            if marker.trim() == name {
+                if body_start.is_some() {
+                    anyhow::bail!("Region '{}' is opened twice", name);
+                }
+                body_start = Some(offset + line.len());
+            }
+        } else if let Some(marker) = trimmed.strip_prefix(END_MARKER) {
+            if marker.trim() == name {
+                let Some(body_start) = body_start else {
+                    anyhow::bail!("Region '{}' ends before it begins", name);
+                };
+                let indent = line[..line.len() - line.trim_start().len()].to_string();
+                return Ok(RegionBounds { body_start, body_end: offset, indent });
+            }
+        }
+        offset += line.len();
+    }
+
+    match body_start {
+        Some(_) => anyhow::bail!("Region '{}' is missing its '{} {}' marker", name, END_MARKER, name),
+        None => anyhow::bail!("Marker '{} {}' not found", BEGIN_MARKER, name),
+    }
+}
+
+/// Returns the body of the region called `name`, excluding the marker lines.
+pub fn region_body<'a>(content: &'a str, name: &str) -> Result<&'a str> {
+    let bounds = find_region(content, name)?;
+    Ok(&content[bounds.body_start..bounds.body_end])
+}
+
+/// Appends `lines` to the end of the region called `name`.
+///
+/// Each non-empty line is indented like the end marker, so callers pass unindented text.
+pub fn append_to_region(content: &str, name: &str, lines: &str) -> Result<String> {
+    let bounds = find_region(content, name)?;
+
+    let mut insertion = String::new();
+    for line in lines.lines() {
+        if !line.is_empty() {
+            insertion.push_str(&bounds.indent);
+        }
+        insertion.push_str(line);
+        insertion.push('\n');
+    }
+
+    Ok(format!(
+        "{}{}{}",
+        &content[..bounds.body_end],
+        insertion,
+        &content[bounds.body_end..]
+    ))
+}
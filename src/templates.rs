@@ -0,0 +1,300 @@
+// src/templates.rs
+//! Registry of project templates used by `jumake new`.
+//!
+//! The built-in templates are compiled into the binary and always available.
+//! User-defined templates are directories containing a `template.toml` manifest and are
+//! loaded from `<config dir>/jumake/templates/` and from the `template_dir` set in the
+//! JuMake configuration. A user template with the same name as a built-in one replaces it.
+
+use crate::context::Context;
+use crate::config::load_config;
+use crate::placeholders::{render, standard_variables};
+use anyhow::{Context as AnyhowContext, Result};
+use log::info;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// File name of the manifest describing a template directory.
+pub const MANIFEST_FILE_NAME: &str = "template.toml";
+
+/// Manifest (`template.toml`) of a user-defined template directory.
+///
+/// ```toml
+/// name = "OurPlugin"
+/// description = "Audio plugin with a parameter tree"
+///
+/// [[files]]
+/// source = "PluginProcessor.cpp"
+/// destination = "src/PluginProcessor.cpp"
+///
+/// [placeholders]
+/// company = "Acme Audio"
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct TemplateManifest {
+    /// Template name; defaults to the directory name.
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub files: Vec<TemplateFileEntry>,
+    /// Placeholder names and their default values.
+    #[serde(default)]
+    pub placeholders: BTreeMap<String, String>,
+}
+
+/// A single file entry of a template manifest.
+#[derive(Debug, Deserialize)]
+pub struct TemplateFileEntry {
+    /// Path of the source file, relative to the template directory.
+    pub source: PathBuf,
+    /// Path of the generated file, relative to the project root.
+    pub destination: PathBuf,
+}
+
+/// Contents of a template file, either embedded in the binary or read from disk.
+#[derive(Debug)]
+enum FileContents {
+    Embedded(&'static [u8]),
+    OnDisk(PathBuf),
+}
+
+#[derive(Debug)]
+struct TemplateFile {
+    destination: PathBuf,
+    contents: FileContents,
+}
+
+/// A project template that can be instantiated into a new project directory.
+#[derive(Debug)]
+pub struct ProjectTemplate {
+    pub name: String,
+    pub description: String,
+    files: Vec<TemplateFile>,
+    placeholders: BTreeMap<String, String>,
+}
+
+impl ProjectTemplate {
+    /// Creates a template from one of the embedded file sets.
+    fn builtin(name: &str, description: &str, files: &'static [(&'static str, &'static [u8])]) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            files: files
+                .iter()
+                .map(|(destination, contents)| TemplateFile {
+                    destination: PathBuf::from(destination),
+                    contents: FileContents::Embedded(contents),
+                })
+                .collect(),
+            placeholders: BTreeMap::new(),
+        }
+    }
+
+    /// Loads a template from a directory containing a `template.toml` manifest.
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+        let manifest: TemplateManifest = toml::from_str(
+            &fs::read_to_string(&manifest_path)
+                .with_context(|| format!("Failed to read {}", manifest_path.display()))?,
+        )
+        .with_context(|| format!("Invalid template manifest {}", manifest_path.display()))?;
+
+        let name = match manifest.name {
+            Some(name) => name,
+            None => dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .ok_or_else(|| anyhow::anyhow!("Cannot derive template name from {}", dir.display()))?,
+        };
+
+        let mut files = Vec::with_capacity(manifest.files.len());
+        for entry in manifest.files {
+            ensure_relative(&entry.source)
+                .and_then(|_| ensure_relative(&entry.destination))
+                .with_context(|| format!("Invalid file entry in {}", manifest_path.display()))?;
+
+            let source = dir.join(&entry.source);
+            if !source.is_file() {
+                anyhow::bail!(
+                    "Template '{}' references missing file: {}",
+                    name,
+                    source.display()
+                );
+            }
+            files.push(TemplateFile {
+                destination: entry.destination,
+                contents: FileContents::OnDisk(source),
+            });
+        }
+
+        Ok(Self {
+            name,
+            description: manifest.description,
+            files,
+            placeholders: manifest.placeholders,
+        })
+    }
+
+    /// Writes all template files into the project directory of `context`.
+    ///
+    /// Placeholders are substituted in file contents and destination paths. The standard
+    /// variables take precedence over the defaults declared in the manifest.
+    pub fn instantiate(&self, context: &Context) -> Result<()> {
+        let mut variables = standard_variables(&context.project_name);
+        for (name, default) in &self.placeholders {
+            variables.entry(name.clone()).or_insert_with(|| default.clone());
+        }
+
+        for file in &self.files {
+            let contents = match &file.contents {
+                FileContents::Embedded(bytes) => bytes.to_vec(),
+                FileContents::OnDisk(path) => fs::read(path)
+                    .with_context(|| format!("Failed to read template file: {}", path.display()))?,
+            };
+
+            // Only substitute placeholders in text files; binary assets are copied as-is.
+            let contents = match String::from_utf8(contents) {
+                Ok(text) => render(&text, &variables)
+                    .with_context(|| format!("Failed to render {}", file.destination.display()))?
+                    .into_bytes(),
+                Err(e) => e.into_bytes(),
+            };
+
+            let destination = render(&file.destination.to_string_lossy(), &variables)?;
+            ensure_relative(Path::new(&destination))?;
+            let path = context.project_path.join(&destination);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+            }
+            fs::write(&path, contents)
+                .with_context(|| format!("Failed to create file: {}", path.display()))?;
+            // Relative, as new projects are created in a staging directory
+            println!("Created file: {}", destination);
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects absolute paths and paths escaping their base directory.
+fn ensure_relative(path: &Path) -> Result<()> {
+    if path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(())
+    } else {
+        anyhow::bail!("Path must be relative and stay inside its directory: {}", path.display())
+    }
+}
+
+/// All known project templates, in display order.
+#[derive(Debug)]
+pub struct TemplateRegistry {
+    templates: Vec<ProjectTemplate>,
+}
+
+impl TemplateRegistry {
+    /// Creates a registry containing only the built-in templates.
+    pub fn builtin() -> Self {
+        Self {
+            templates: vec![
+                ProjectTemplate::builtin("GuiApplication", "JUCE GUI application", GUI_APPLICATION_FILES),
+                ProjectTemplate::builtin("AudioPlugin", "JUCE audio plugin", AUDIO_PLUGIN_FILES),
+                ProjectTemplate::builtin("ConsoleApp", "JUCE console application", CONSOLE_APP_FILES),
+            ],
+        }
+    }
+
+    /// Creates a registry with the built-in templates plus all user-defined templates
+    /// found in the default template directory and the configured `template_dir`.
+    pub fn load() -> Result<Self> {
+        let mut registry = Self::builtin();
+
+        if let Some(config_dir) = dirs::config_dir() {
+            registry.add_search_dir(&config_dir.join("jumake").join("templates"))?;
+        }
+        if let Some(template_dir) = load_config()?.template_dir {
+            registry.add_search_dir(&template_dir)?;
+        }
+
+        Ok(registry)
+    }
+
+    /// Registers every template directory found directly below `dir`.
+    ///
+    /// Missing directories are ignored; broken templates are skipped with a warning on stderr
+    /// so that one bad template doesn't make the others unusable.
+    pub fn add_search_dir(&mut self, dir: &Path) -> Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read template directory: {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.join(MANIFEST_FILE_NAME).is_file())
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            match ProjectTemplate::from_dir(&path) {
+                Ok(template) => {
+                    info!("Loaded template '{}' from {}", template.name, path.display());
+                    self.insert(template);
+                }
+                Err(e) => eprintln!("⚠️  Skipping template at {}: {:#}", path.display(), e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds a template, replacing any existing template with the same name.
+    pub fn insert(&mut self, template: ProjectTemplate) {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+    }
+
+    /// Looks up a template by name.
+    pub fn get(&self, name: &str) -> Option<&ProjectTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    /// All registered templates, in display order.
+    pub fn templates(&self) -> &[ProjectTemplate] {
+        &self.templates
+    }
+
+    /// Names of all registered templates, in display order.
+    pub fn names(&self) -> Vec<&str> {
+        self.templates.iter().map(|t| t.name.as_str()).collect()
+    }
+}
+
+// ======================= BUILT-IN TEMPLATES ========================
+const GUI_APPLICATION_FILES: &[(&str, &[u8])] = &[
+    ("src/Main.cpp", include_bytes!("../templates/GuiApplicationTemplate/Main.cpp.template")),
+    ("src/MainComponent.cpp", include_bytes!("../templates/GuiApplicationTemplate/MainComponent.cpp.template")),
+    ("src/MainComponent.h", include_bytes!("../templates/GuiApplicationTemplate/MainComponent.h.template")),
+    ("src/CMakeLists.txt", include_bytes!("../templates/GuiApplicationTemplate/CMakeLists.txt.template")),
+];
+
+const AUDIO_PLUGIN_FILES: &[(&str, &[u8])] = &[
+    ("src/PluginProcessor.cpp", include_bytes!("../templates/AudioPluginTemplate/PluginProcessor.cpp.template")),
+    ("src/PluginProcessor.h", include_bytes!("../templates/AudioPluginTemplate/PluginProcessor.h.template")),
+    ("src/PluginEditor.cpp", include_bytes!("../templates/AudioPluginTemplate/PluginEditor.cpp.template")),
+    ("src/PluginEditor.h", include_bytes!("../templates/AudioPluginTemplate/PluginEditor.h.template")),
+    ("src/ParameterIDs.h", include_bytes!("../templates/AudioPluginTemplate/ParameterIDs.h.template")),
+    ("src/CMakeLists.txt", include_bytes!("../templates/AudioPluginTemplate/CMakeLists.txt.template")),
+];
+
+const CONSOLE_APP_FILES: &[(&str, &[u8])] = &[
+    ("src/Main.cpp", include_bytes!("../templates/ConsoleAppTemplate/Main.cpp.template")),
+    ("src/CMakeLists.txt", include_bytes!("../templates/ConsoleAppTemplate/CMakeLists.txt.template")),
+];
@@ -51,12 +51,31 @@
 // tests/integration_test.rs
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use jumake::{
-    create_files::{create_cmakelists, create_source_files},
+    cmake_edit::CMakeDocument,
+    config::{JuMakeConfig, CONFIG_KEYS},
+    create_files::{add_class, create_cmakelists, create_source_files},
+    create_project::create_project,
     context::Context,
+    build::{build_command, build_directory, configure_command, configured_build_dirs, is_multi_config, BuildError, BuildOptions},
+    clean::{clean_project, CleanMode, CleanOptions},
+    diagnostics::{Diagnostics, Severity},
+    doctor::{check_build_dir, version_at_least, Check, Report, Status},
+    error::{CliError, ErrorKind, JuMakeError},
+    juce::{read_juce_version, resolve_source, switch_juce_source, JuceMode},
+    juce_modules::{add_custom_module, add_module, available_modules, linked_modules, remove_module, ModuleDeclaration},
+    manage_classes::{remove_class, rename_class, EditOptions},
+    initialize_git::{create_initial_commit, initialize_git_repo, restore_juce},
+    manifest::{BuildOverrides, JuceSource, ProjectManifest},
+    parameters::{add_parameter, ParameterSpec},
+    placeholders::{render, PlaceholderError, Variables},
+    presets::{find_preset, generate_presets, write_presets, USER_PRESETS_FILE},
+    templates::TemplateRegistry,
 };
+use indoc::indoc;
 use lazy_static::lazy_static;
+use serde_json::Value;
 
 // Test data structure
 struct TestData {
@@ -65,6 +84,29 @@ struct TestData {
     expected_source_files: Vec<&'static str>,
 }
 
+/// Creates a git repository standing in for JUCE, with one tagged commit per version.
+fn create_juce_repository(path: &PathBuf, versions: &[&str]) {
+    let _ = fs::remove_dir_all(path);
+    let repo = git2::Repository::init(path).expect("Failed to create JUCE repository");
+    let signature = git2::Signature::now("Test", "test@example.com").expect("Failed to create signature");
+    let mut parent: Option<git2::Oid> = None;
+    for version in versions {
+        fs::write(path.join("CMakeLists.txt"), format!("project(JUCE VERSION {})\n", version))
+            .expect("Failed to write JUCE CMakeLists.txt");
+        let mut index = repo.index().expect("Failed to open index");
+        index.add_path(std::path::Path::new("CMakeLists.txt")).expect("Failed to stage file");
+        let tree = repo.find_tree(index.write_tree().expect("Failed to write tree")).expect("Failed to find tree");
+        let parents: Vec<git2::Commit> = parent.iter().map(|id| repo.find_commit(*id).expect("Missing commit")).collect();
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+        let commit = repo
+            .commit(Some("HEAD"), &signature, &signature, version, &tree, &parent_refs)
+            .expect("Failed to commit");
+        repo.tag_lightweight(version, &repo.find_object(commit, None).expect("Missing commit"), false)
+            .expect("Failed to tag");
+        parent = Some(commit);
+    }
+}
+
 // Define test data for each template
 lazy_static! {
     static ref TEST_DATA: [TestData; 3] = [
@@ -99,6 +141,7 @@ fn test_create_cmakelists() {
             project_path: PathBuf::from("/tmp/cmake_test_project"),
             template_name: Some(String::from(data.template_name)),
             build_type: String::from("Release"),
+            manifest: ProjectManifest::new("test_project", None),
         };
         let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
         fs::create_dir_all(&context.project_path)
@@ -128,6 +171,7 @@ fn test_create_source_files() {
             project_path: PathBuf::from("/tmp/sourcefile_test_project"),
             template_name: Some(String::from(data.template_name)),
             build_type: String::from("Release"),
+            manifest: ProjectManifest::new("test_project", None),
         };
         let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
         fs::create_dir_all(&context.project_path)
@@ -146,8 +190,1435 @@ fn test_create_source_files() {
                 src_path.display()
             );
         }
+        if data.template_name == "AudioPlugin" {
+            // The comments after the substituted four-character codes line up with their neighbours
+            let cmakelists = fs::read_to_string(src_path.join("CMakeLists.txt")).expect("Failed to read CMakeLists.txt");
+            let columns: Vec<_> = cmakelists
+                .lines()
+                .filter(|line| ["COPY_PLUGIN_AFTER_BUILD", "PLUGIN_"].iter().any(|key| line.trim_start().starts_with(key)))
+                .map(|line| line.find('#'))
+                .collect();
+            assert_eq!(columns.len(), 3);
+            assert!(columns.iter().all(|column| *column == Some(48)), "Misaligned comments:\n{}", cmakelists);
+        }
 
         fs::remove_dir_all(&context.project_path)
             .expect("Failed to clean up test project directory");
     }
 }
+
+#[test]
+fn test_user_template_directory() {
+    let templates_dir = PathBuf::from("/tmp/jumake_user_templates");
+    let template_dir = templates_dir.join("OurPlugin");
+    let _ = fs::remove_dir_all(&templates_dir); // Clean up before running the test
+    fs::create_dir_all(&template_dir).expect("Failed to create template directory");
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+            description = "House plugin template"
+
+            [[files]]
+            source = "Main.cpp"
+            destination = "src/Main.cpp"
+
+            [placeholders]
+            company = "Acme Audio"
+        "#,
+    )
+    .expect("Failed to write template manifest");
+    fs::write(template_dir.join("Main.cpp"), "// {{project_name}} by {{company}}\n")
+        .expect("Failed to write template file");
+
+    let mut registry = TemplateRegistry::builtin();
+    registry
+        .add_search_dir(&templates_dir)
+        .expect("Failed to load user templates");
+    assert!(registry.get("GuiApplication").is_some());
+    let template = registry.get("OurPlugin").expect("User template not registered");
+    assert_eq!(template.description, "House plugin template");
+
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/user_template_test_project"),
+        template_name: Some(String::from("OurPlugin")),
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("test_project", None),
+    };
+    let _ = fs::remove_dir_all(&context.project_path);
+    template.instantiate(&context).expect("Failed to instantiate template");
+
+    let content = fs::read_to_string(context.project_path.join("src").join("Main.cpp"))
+        .expect("Failed to read generated file");
+    assert_eq!(content, "// test_project by Acme Audio\n");
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+    fs::remove_dir_all(&templates_dir).expect("Failed to clean up template directory");
+}
+
+#[test]
+fn test_render_placeholders() {
+    let mut variables = Variables::new();
+    variables.insert(String::from("class_name"), String::from("Filter"));
+    variables.insert(String::from("namespace"), String::from("dsp"));
+    variables.insert(String::from("author"), String::new());
+
+    let template = "{{#if namespace}}\nnamespace {{ namespace }}\n{\n{{/if}}\nclass {{class_name}} {};\n\
+                    // {{#if author}}by {{author}}{{else}}anonymous{{/if}}\n\
+                    juce::Template t {{1, 2}}; \\{{class_name}}\n";
+    let rendered = render(template, &variables).expect("Failed to render template");
+    assert_eq!(
+        rendered,
+        "namespace dsp\n{\nclass Filter {};\n// anonymous\njuce::Template t {{1, 2}}; {{class_name}}\n"
+    );
+
+    let error = render("line one\n{{missing}}", &variables).unwrap_err();
+    assert!(matches!(error, PlaceholderError::UnknownVariable { ref name, line: 2, .. } if name == "missing"));
+    assert!(matches!(
+        render("{{#if namespace}}open", &variables),
+        Err(PlaceholderError::UnterminatedBlock { .. })
+    ));
+}
+
+#[test]
+fn test_add_element_types() {
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/add_class_test_project"),
+        template_name: Some(String::from("AudioPlugin")),
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("test_project", None),
+    };
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    create_source_files(&context).expect("Failed to create source files");
+
+    add_class(&context, "processor", "Filter", None).expect("Failed to add processor");
+    add_class(&context, "timer", "MeterTimer", None).expect("Failed to add timer");
+
+    let src_path = context.project_path.join("src");
+    let header = fs::read_to_string(src_path.join("FilterProcessor.h")).expect("Failed to read header");
+    assert!(header.contains("class FilterProcessor: public juce::dsp::ProcessorBase"));
+    assert!(src_path.join("MeterTimer.cpp").exists());
+
+    let cmakelists = fs::read_to_string(src_path.join("CMakeLists.txt")).expect("Failed to read CMakeLists.txt");
+    assert!(cmakelists.contains("FilterProcessor.cpp"));
+    assert!(cmakelists.contains("MeterTimer.cpp"));
+    assert!(add_class(&context, "processor", "FilterProcessor", None).is_err());
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_add_parameter() {
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/add_parameter_test_project"),
+        template_name: Some(String::from("AudioPlugin")),
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("test_project", None),
+    };
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    create_source_files(&context).expect("Failed to create source files");
+
+    let spec = ParameterSpec::new("gain", Some("-60..12"), Some(0.0), Some(String::from("dB")), true)
+        .expect("Failed to build parameter spec");
+    add_parameter(&context, &spec).expect("Failed to add parameter");
+
+    let src_path = context.project_path.join("src");
+    let ids = fs::read_to_string(src_path.join("ParameterIDs.h")).expect("Failed to read ParameterIDs.h");
+    assert!(ids.contains("    inline const juce::ParameterID gain { \"gain\", 1 };\n    // jumake:end parameter-ids"));
+
+    let processor = fs::read_to_string(src_path.join("PluginProcessor.cpp")).expect("Failed to read PluginProcessor.cpp");
+    assert!(processor.contains("juce::NormalisableRange<float> (-60.0f, 12.0f)"));
+    assert!(processor.contains("withLabel (\"dB\")"));
+
+    let editor = fs::read_to_string(src_path.join("PluginEditor.h")).expect("Failed to read PluginEditor.h");
+    assert!(editor.contains("juce::Slider gainSlider;"));
+
+    assert!(add_parameter(&context, &spec).is_err(), "Duplicate parameter must be rejected");
+    for range in ["1..0", "1..1", "-0.5..-0.5"] {
+        let error = ParameterSpec::new("gain", Some(range), None, None, false).expect_err(range);
+        assert!(error.to_string().contains("minimum must be less than maximum"), "{}: {}", range, error);
+    }
+    for default in [2.0, -0.1, 1.01] {
+        let error = ParameterSpec::new("gain", Some("0..1"), Some(default), None, false).expect_err("Out of range");
+        assert!(error.to_string().contains("is outside the range 0..1"), "{}: {}", default, error);
+    }
+    ParameterSpec::new("gain", Some("0..1"), Some(1.0), None, false).expect("The range includes its maximum");
+    for range in ["nan..1", "0..inf", "-inf..0", "0..NaN", "-infinity..infinity"] {
+        let error = ParameterSpec::new("gain", Some(range), None, None, false).expect_err(range);
+        assert!(error.to_string().contains("must be finite numbers"), "{}: {}", range, error);
+    }
+    for default in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        let error = ParameterSpec::new("gain", Some("0..1"), Some(default), None, false).expect_err("Not finite");
+        assert!(error.to_string().contains("must be a finite number"), "{}: {}", default, error);
+    }
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_add_class_in_subdirectory() {
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/add_subdir_test_project"),
+        template_name: Some(String::from("ConsoleApp")),
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("test_project", None),
+    };
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    create_source_files(&context).expect("Failed to create source files");
+    let src_path = context.project_path.join("src");
+
+    add_class(&context, "class", "dsp/Filter", Some("dsp")).expect("Failed to add class");
+    let header = fs::read_to_string(src_path.join("dsp").join("Filter.h")).expect("Failed to read header");
+    assert!(header.contains("namespace dsp\n{\n\nclass Filter\n"));
+    assert!(header.contains("} // namespace dsp\n"));
+    let cmakelists = fs::read_to_string(src_path.join("CMakeLists.txt")).expect("Failed to read CMakeLists.txt");
+    assert!(cmakelists.contains("        dsp/Filter.cpp\n"));
+
+    // A subdirectory with its own CMakeLists.txt gets the entry relative to itself
+    fs::create_dir_all(src_path.join("ui")).expect("Failed to create ui directory");
+    fs::write(
+        src_path.join("ui").join("CMakeLists.txt"),
+        "target_sources(${PROJECT_NAME}\n    PRIVATE\n        Existing.cpp)\n",
+    )
+    .expect("Failed to write ui/CMakeLists.txt");
+    add_class(&context, "component", "ui/widgets/Knob", None).expect("Failed to add component");
+    let ui_cmakelists = fs::read_to_string(src_path.join("ui").join("CMakeLists.txt"))
+        .expect("Failed to read ui/CMakeLists.txt");
+    assert!(ui_cmakelists.contains("widgets/KnobComponent.cpp"));
+
+    // A CMakeLists.txt that can't be updated leaves no files behind, so adding the class again works
+    fs::create_dir_all(src_path.join("fx")).expect("Failed to create fx directory");
+    fs::write(src_path.join("fx").join("CMakeLists.txt"), "add_subdirectory(extra)\n")
+        .expect("Failed to write fx/CMakeLists.txt");
+    assert!(add_class(&context, "class", "fx/reverb/Reverb", None).is_err());
+    assert!(!src_path.join("fx").join("reverb").exists());
+    fs::write(src_path.join("fx").join("CMakeLists.txt"), "target_sources(${PROJECT_NAME} PRIVATE)\n")
+        .expect("Failed to write fx/CMakeLists.txt");
+    add_class(&context, "class", "fx/reverb/Reverb", None).expect("Failed to add class after fixing CMakeLists.txt");
+    assert!(src_path.join("fx").join("reverb").join("Reverb.cpp").exists());
+
+    assert!(add_class(&context, "class", "../Escape", None).is_err());
+    assert!(add_class(&context, "class", "Valid", Some("not a namespace")).is_err());
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_rename_and_remove_class() {
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/rename_remove_test_project"),
+        template_name: Some(String::from("GuiApplication")),
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("test_project", None),
+    };
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    create_source_files(&context).expect("Failed to create source files");
+    let src_path = context.project_path.join("src");
+
+    add_class(&context, "class", "dsp/Filter", None).expect("Failed to add class");
+    let main_component = src_path.join("MainComponent.h");
+    let content = fs::read_to_string(&main_component).expect("Failed to read MainComponent.h");
+    fs::write(&main_component, content.replace("#pragma once\n", "#pragma once\n#include \"dsp/Filter.h\"\n"))
+        .expect("Failed to write MainComponent.h");
+
+    // A dry run changes nothing
+    let dry_run = EditOptions { dry_run: true, stage: false };
+    rename_class(&context, "dsp/Filter", "ui/LowPass", dry_run).expect("Dry run failed");
+    assert!(src_path.join("dsp").join("Filter.h").exists());
+
+    rename_class(&context, "dsp/Filter", "ui/LowPass", EditOptions::default()).expect("Failed to rename class");
+    assert!(!src_path.join("dsp").join("Filter.h").exists());
+    let header = fs::read_to_string(src_path.join("ui").join("LowPass.h")).expect("Failed to read renamed header");
+    assert!(header.contains("class LowPass\n") && !header.contains("Filter"));
+    let cpp = fs::read_to_string(src_path.join("ui").join("LowPass.cpp")).expect("Failed to read renamed source");
+    assert!(cpp.contains("#include \"LowPass.h\"") && cpp.contains("LowPass::LowPass()"));
+    let main_header = fs::read_to_string(&main_component).expect("Failed to read MainComponent.h");
+    assert!(main_header.contains("#include \"ui/LowPass.h\""));
+    let cmakelists = fs::read_to_string(src_path.join("CMakeLists.txt")).expect("Failed to read CMakeLists.txt");
+    assert!(cmakelists.contains("ui/LowPass.cpp") && !cmakelists.contains("dsp/Filter.cpp"));
+
+    remove_class(&context, "ui/LowPass", EditOptions::default()).expect("Failed to remove class");
+    assert!(!src_path.join("ui").join("LowPass.cpp").exists());
+    let cmakelists = fs::read_to_string(src_path.join("CMakeLists.txt")).expect("Failed to read CMakeLists.txt");
+    assert!(!cmakelists.contains("LowPass"));
+    assert!(cmakelists.contains("MainComponent.cpp)"));
+
+    assert!(remove_class(&context, "Missing", EditOptions::default()).is_err());
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_cmake_document_editing() {
+    // One-line target_sources, quoted arguments and a trailing newline
+    let mut document = CMakeDocument::parse("target_sources(${PROJECT_NAME} PRIVATE \"b.cpp\" c.cpp)\n")
+        .expect("Failed to parse CMake file");
+    let id = document.find_command("target_sources", Some("${PROJECT_NAME}")).expect("Command not found");
+    document.insert_argument(id, "PRIVATE", "a.cpp").expect("Failed to insert argument");
+    assert!(document.remove_argument(id, "PRIVATE", "c.cpp").expect("Failed to remove argument"));
+    assert_eq!(document.into_string(), "target_sources(${PROJECT_NAME} PRIVATE a.cpp \"b.cpp\")\n");
+
+    // Comments between arguments and ${CMAKE_CURRENT_SOURCE_DIR} prefixes
+    let source = indoc! {"
+        # Sources
+        target_sources(${PROJECT_NAME}
+            # the main sources
+            PRIVATE
+                ${CMAKE_CURRENT_SOURCE_DIR}/Main.cpp  # entry point
+                ${CMAKE_CURRENT_SOURCE_DIR}/Zeta.cpp)
+        target_link_libraries(${PROJECT_NAME} PRIVATE juce::juce_core)
+    "};
+    let mut document = CMakeDocument::parse(source).expect("Failed to parse CMake file");
+    let id = document.find_command("target_sources", None).expect("Command not found");
+    assert!(document.contains(id, "PRIVATE", "Zeta.cpp"));
+    document
+        .insert_argument(id, "PRIVATE", "${CMAKE_CURRENT_SOURCE_DIR}/Synth.cpp")
+        .expect("Failed to insert argument");
+    assert!(document.remove_argument(id, "PRIVATE", "Main.cpp").expect("Failed to remove argument"));
+    document.insert_argument(id, "PUBLIC", "Api.cpp").expect("Failed to insert section");
+    assert_eq!(
+        document.into_string(),
+        indoc! {"
+            # Sources
+            target_sources(${PROJECT_NAME}
+                # the main sources
+                PRIVATE
+                    ${CMAKE_CURRENT_SOURCE_DIR}/Synth.cpp
+                    ${CMAKE_CURRENT_SOURCE_DIR}/Zeta.cpp
+                PUBLIC
+                    Api.cpp)
+            target_link_libraries(${PROJECT_NAME} PRIVATE juce::juce_core)
+        "}
+    );
+
+    let mut document = CMakeDocument::parse("target_sources(app PRIVATE c.cpp a.cpp PUBLIC z.cpp b.cpp)")
+        .expect("Failed to parse CMake file");
+    let id = document.find_command("TARGET_SOURCES", Some("app")).expect("Command not found");
+    document.sort_section(id, "PRIVATE").expect("Failed to sort section");
+    assert_eq!(document.into_string(), "target_sources(app PRIVATE a.cpp c.cpp PUBLIC z.cpp b.cpp)");
+
+    // Sorted sections stay sorted, with comments following their arguments
+    let mut document = CMakeDocument::parse(indoc! {"
+        target_link_libraries(app
+            PRIVATE
+                juce::juce_audio_utils # for the player
+                juce::juce_gui_basics
+                our_utils # in-house
+            PUBLIC
+                juce::juce_recommended_config_flags)
+    "})
+    .expect("Failed to parse CMake file");
+    let id = document.find_command("target_link_libraries", Some("app")).expect("Command not found");
+    document.insert_argument(id, "PRIVATE", "juce::juce_dsp").expect("Failed to insert argument");
+    document.insert_argument(id, "PRIVATE", "zz_utils").expect("Failed to insert argument");
+    assert_eq!(
+        document.into_string(),
+        indoc! {"
+            target_link_libraries(app
+                PRIVATE
+                    juce::juce_audio_utils # for the player
+                    juce::juce_dsp
+                    juce::juce_gui_basics
+                    our_utils # in-house
+                    zz_utils
+                PUBLIC
+                    juce::juce_recommended_config_flags)
+        "}
+    );
+
+    assert!(CMakeDocument::parse("target_sources(${PROJECT_NAME} PRIVATE a.cpp\n").is_err());
+}
+
+#[test]
+fn test_manage_juce_modules() {
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/juce_modules_test_project"),
+        template_name: Some(String::from("AudioPlugin")),
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("test_project", None),
+    };
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    create_source_files(&context).expect("Failed to create source files");
+
+    // Fake JUCE checkout linked into the project
+    let juce_path = context.project_path.join("modules").join("JUCE");
+    for module in ["juce_audio_utils", "juce_dsp", "juce_osc"] {
+        let module_path = juce_path.join("modules").join(module);
+        fs::create_dir_all(&module_path).expect("Failed to create fake JUCE module");
+        fs::write(module_path.join(format!("{}.h", module)), "").expect("Failed to write module header");
+    }
+    fs::create_dir_all(juce_path.join("modules").join("not_a_module")).expect("Failed to create directory");
+
+    assert_eq!(
+        available_modules(&juce_path).expect("Failed to list modules"),
+        vec!["juce_audio_utils", "juce_dsp", "juce_osc"]
+    );
+    assert_eq!(linked_modules(&context).expect("Failed to read linked modules"), vec!["juce_audio_utils"]);
+
+    add_module(&context, "juce_dsp").expect("Failed to add module");
+    add_module(&context, "juce::juce_osc").expect("Failed to add module");
+    assert!(add_module(&context, "juce_dsp").is_err(), "Duplicate module must be rejected");
+    assert!(add_module(&context, "juce_nonexistent").is_err(), "Unknown module must be rejected");
+
+    let cmakelists = fs::read_to_string(context.project_path.join("src").join("CMakeLists.txt"))
+        .expect("Failed to read CMakeLists.txt");
+    assert!(cmakelists.contains("        juce::juce_audio_utils\n        juce::juce_dsp\n        juce::juce_osc\n    PUBLIC"));
+
+    remove_module(&context, "juce_osc").expect("Failed to remove module");
+    assert!(remove_module(&context, "juce_osc").is_err(), "Unlinked module cannot be removed");
+    assert_eq!(
+        linked_modules(&context).expect("Failed to read linked modules"),
+        vec!["juce_audio_utils", "juce_dsp"]
+    );
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_custom_juce_module() {
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/custom_module_test_project"),
+        template_name: Some(String::from("GuiApplication")),
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("test_project", None),
+    };
+    let shared_path = PathBuf::from("/tmp/custom_module_test_shared");
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    let _ = fs::remove_dir_all(&shared_path);
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    git2::Repository::init(&context.project_path).expect("Failed to create repository");
+    create_cmakelists(&context).expect("Failed to create CMakeLists.txt");
+    create_source_files(&context).expect("Failed to create source files");
+
+    let juce_module = context.project_path.join("modules/JUCE/modules/juce_dsp");
+    fs::create_dir_all(&juce_module).expect("Failed to create fake JUCE module");
+    fs::write(juce_module.join("juce_dsp.h"), "").expect("Failed to write module header");
+
+    let module_path = shared_path.join("our_dsp_utils");
+    fs::create_dir_all(&module_path).expect("Failed to create custom module");
+    let header = indoc! {"
+        /*
+         BEGIN_JUCE_MODULE_DECLARATION
+
+          ID:               our_dsp_utils
+          vendor:           us
+          dependencies:     juce_dsp, juce_missing
+
+         END_JUCE_MODULE_DECLARATION
+        */
+        #pragma once
+    "};
+    fs::write(module_path.join("our_dsp_utils.h"), header).expect("Failed to write module header");
+
+    let declaration = ModuleDeclaration::parse(header).expect("Failed to parse module declaration");
+    assert_eq!(declaration.id, "our_dsp_utils");
+    assert_eq!(declaration.dependencies, vec!["juce_dsp", "juce_missing"]);
+    assert!(add_custom_module(&context, &module_path, false).is_err(), "Unknown dependency must be rejected");
+
+    fs::write(module_path.join("our_dsp_utils.h"), header.replace(", juce_missing", ""))
+        .expect("Failed to write module header");
+    add_custom_module(&context, &module_path, false).expect("Failed to add custom module");
+
+    let link = context.project_path.join("modules/our_dsp_utils");
+    assert!(link.symlink_metadata().expect("Module link missing").file_type().is_symlink());
+    let root = fs::read_to_string(context.project_path.join("CMakeLists.txt")).expect("Failed to read CMakeLists.txt");
+    assert!(root.contains("add_subdirectory(modules/JUCE)\njuce_add_module(modules/our_dsp_utils)\nadd_subdirectory(src)\n"));
+    assert!(linked_modules(&context).expect("Failed to read linked modules").contains(&String::from("our_dsp_utils")));
+    assert!(add_custom_module(&context, &module_path, false).is_err(), "Duplicate module must be rejected");
+    let repo = git2::Repository::open(&context.project_path).expect("Failed to open repository");
+    assert!(repo.is_path_ignored("modules/our_dsp_utils").expect("Failed to check ignore rules"), "Links are ignored");
+
+    remove_module(&context, "our_dsp_utils").expect("Failed to remove module");
+    assert!(link.symlink_metadata().is_err(), "Module link must be removed");
+    let root = fs::read_to_string(context.project_path.join("CMakeLists.txt")).expect("Failed to read CMakeLists.txt");
+    assert!(!root.contains("juce_add_module"));
+
+    // A copied module is committed with the project, also where all of modules/ used to be ignored
+    fs::write(context.project_path.join(".gitignore"), "modules/\njumake_build/\nmodules/our_dsp_utils\n")
+        .expect("Failed to write .gitignore");
+    add_custom_module(&context, &module_path, true).expect("Failed to copy custom module");
+    assert!(context.project_path.join("modules/our_dsp_utils/our_dsp_utils.h").is_file());
+    let gitignore = fs::read_to_string(context.project_path.join(".gitignore")).expect("Failed to read .gitignore");
+    assert_eq!(gitignore, "jumake_build/\nmodules/JUCE\n");
+    let repo = git2::Repository::open(&context.project_path).expect("Failed to open repository");
+    assert!(!repo.is_path_ignored("modules/our_dsp_utils/our_dsp_utils.h").expect("Failed to check ignore rules"));
+    assert!(repo.is_path_ignored("modules/JUCE").expect("Failed to check ignore rules"));
+    remove_module(&context, "our_dsp_utils").expect("Failed to remove module");
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+    fs::remove_dir_all(&shared_path).expect("Failed to clean up shared module directory");
+}
+
+#[test]
+fn test_project_manifest() {
+    let mut manifest = ProjectManifest::new("legacy_project", None);
+    manifest.project.version = String::from("1.2.3");
+    let context = Context {
+        project_name: String::from("legacy_project"),
+        project_path: PathBuf::from("/tmp/manifest_test_project"),
+        template_name: Some(String::from("AudioPlugin")),
+        build_type: String::from("Release"),
+        manifest,
+    };
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    create_cmakelists(&context).expect("Failed to create CMakeLists.txt");
+    create_source_files(&context).expect("Failed to create source files");
+
+    // A project from before jumake.toml only has the .jumake build-type file
+    fs::write(context.project_path.join(".jumake"), "Debug").expect("Failed to write .jumake");
+    let loaded = Context::load(context.project_path.clone()).expect("Failed to migrate project");
+    assert_eq!(loaded.project_name, "legacy_project");
+    assert_eq!(loaded.template_name.as_deref(), Some("AudioPlugin"));
+    assert_eq!(loaded.build_type, "Debug");
+    assert_eq!(loaded.manifest.project.version, "1.2.3");
+    assert!(context.project_path.join("jumake.toml").is_file());
+    assert!(!context.project_path.join(".jumake").exists());
+
+    // Only JuMake projects are migrated: by their JUMAKE_TEMPLATE marker, or their .jumake file,
+    // whose build type must be valid
+    let other_path = PathBuf::from("/tmp/manifest_test_other_project");
+    let _ = fs::remove_dir_all(&other_path);
+    fs::create_dir_all(other_path.join("src")).expect("Failed to create test project directory");
+    fs::write(other_path.join("CMakeLists.txt"), "project(other_project)\nadd_subdirectory(src)\n")
+        .expect("Failed to write CMakeLists.txt");
+    fs::write(other_path.join("src/CMakeLists.txt"), "add_executable(other main.cpp)\n").expect("Failed to write CMakeLists.txt");
+    let error = ProjectManifest::load(&other_path).expect_err("Not a JuMake project");
+    assert!(error.to_string().contains("is not a JuMake project"), "{}", error);
+    assert!(!other_path.join("jumake.toml").exists());
+    fs::write(other_path.join(".jumake"), "Fastest\n").expect("Failed to write .jumake");
+    let migrated = ProjectManifest::load(&other_path).expect("Failed to migrate project");
+    assert_eq!(migrated.project.name, "other_project");
+    assert_eq!(migrated.build.build_type, "Release", "Invalid build types fall back to the default");
+    fs::remove_dir_all(&other_path).expect("Failed to clean up test project directory");
+
+    let mut manifest = loaded.manifest.clone();
+    manifest.build.generator = Some(String::from("Ninja"));
+    manifest.build.cmake_args = ["-DJUCE_ENABLE_MODULE_SOURCE_GROUPS=ON", "-D", "USE_ASAN=ON", "-Wno-dev"]
+        .map(String::from)
+        .to_vec();
+    manifest.build.define("USE_ASAN:BOOL=OFF").expect("Failed to define USE_ASAN");
+    manifest.build.define("JUCE_BUILD_EXTRAS=ON").expect("Failed to define JUCE_BUILD_EXTRAS");
+    assert_eq!(
+        manifest.build.cmake_args,
+        ["-DJUCE_ENABLE_MODULE_SOURCE_GROUPS=ON", "-Wno-dev", "-DUSE_ASAN:BOOL=OFF", "-DJUCE_BUILD_EXTRAS=ON"],
+        "A new definition replaces the earlier one"
+    );
+    assert!(manifest.build.define("USE_ASAN").is_err(), "Needs a value");
+    assert!(manifest.build.define("=ON").is_err(), "Needs a name");
+    manifest.build.toolchain = Some(String::from("cmake/clang.cmake"));
+    manifest.build.target = Some(String::from("legacy_project_VST3"));
+    manifest.build.jobs = Some(4);
+    manifest.build.build_args = vec![String::from("--verbose")];
+    manifest.write(&context.project_path).expect("Failed to write jumake.toml");
+    assert_eq!(ProjectManifest::read(&context.project_path).expect("Failed to read jumake.toml"), manifest);
+
+    fs::write(context.project_path.join("jumake.toml"), "[project]\nname = \"minimal\"\n").expect("Failed to write jumake.toml");
+    let minimal = ProjectManifest::read(&context.project_path).expect("Failed to read jumake.toml");
+    assert_eq!(minimal, ProjectManifest::new("minimal", None));
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_juce_git_submodule() {
+    assert_eq!("symlink".parse::<JuceSource>().expect("Failed to parse JUCE source"), JuceSource::Symlink { version: None });
+    assert_eq!(
+        "git:git@github.com:juce-framework/JUCE.git@8.0.4".parse::<JuceSource>().expect("Failed to parse JUCE source"),
+        JuceSource::Submodule {
+            url: String::from("git@github.com:juce-framework/JUCE.git"),
+            rev: Some(String::from("8.0.4")),
+        }
+    );
+    assert_eq!(
+        "git:https://github.com/juce-framework/JUCE.git".parse::<JuceSource>().expect("Failed to parse JUCE source"),
+        JuceSource::Submodule { url: String::from("https://github.com/juce-framework/JUCE.git"), rev: None }
+    );
+    assert!("copy".parse::<JuceSource>().is_err());
+
+    // A local stand-in for the JUCE repository with two releases
+    let juce_repo_path = PathBuf::from("/tmp/juce_submodule_test_upstream");
+    create_juce_repository(&juce_repo_path, &["8.0.4", "8.0.5"]);
+
+    let mut manifest = ProjectManifest::new("test_project", Some(String::from("ConsoleApp")));
+    manifest.juce = format!("git:file://{}@8.0.4", juce_repo_path.display()).parse().expect("Failed to parse JUCE source");
+    let context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/juce_submodule_test_project"),
+        template_name: Some(String::from("ConsoleApp")),
+        build_type: String::from("Release"),
+        manifest,
+    };
+    let clone_path = PathBuf::from("/tmp/juce_submodule_test_clone");
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    let _ = fs::remove_dir_all(&clone_path);
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    context.manifest.write(&context.project_path).expect("Failed to write jumake.toml");
+    create_cmakelists(&context).expect("Failed to create CMakeLists.txt");
+    create_source_files(&context).expect("Failed to create source files");
+    initialize_git_repo(&context).expect("Failed to initialize Git repository");
+    let repo = git2::Repository::open(&context.project_path).expect("Failed to open project repository");
+    let mut git_config = repo.config().expect("Failed to open Git config");
+    git_config.set_str("user.name", "Jane Doe").expect("Failed to set user.name");
+    git_config.set_str("user.email", "jane@example.org").expect("Failed to set user.email");
+    create_initial_commit(&context, "Start MyPlugin").expect("Failed to create initial commit");
+    let head = repo.head().and_then(|head| head.peel_to_commit()).expect("No initial commit");
+    assert_eq!(head.author().name(), Some("Jane Doe"));
+    assert_eq!(head.author().email(), Some("jane@example.org"));
+    assert_eq!(head.message(), Some("Start MyPlugin"));
+
+    let juce_cmakelists = context.project_path.join("modules/JUCE/CMakeLists.txt");
+    assert_eq!(fs::read_to_string(&juce_cmakelists).expect("JUCE not checked out"), "project(JUCE VERSION 8.0.4)\n");
+    let gitmodules = fs::read_to_string(context.project_path.join(".gitmodules")).expect("Failed to read .gitmodules");
+    assert!(gitmodules.contains("path = modules/JUCE"));
+    let gitignore = fs::read_to_string(context.project_path.join(".gitignore")).expect("Failed to read .gitignore");
+    assert!(!gitignore.contains("modules/"));
+
+    // A fresh clone gets JUCE back at the recorded commit
+    git2::Repository::clone(&format!("file://{}", context.project_path.display()), &clone_path)
+        .expect("Failed to clone project");
+    let cloned = Context::load(clone_path.clone()).expect("Failed to load cloned project");
+    assert!(!clone_path.join("modules/JUCE/CMakeLists.txt").exists());
+    restore_juce(&cloned).expect("Failed to restore JUCE");
+    assert_eq!(
+        fs::read_to_string(clone_path.join("modules/JUCE/CMakeLists.txt")).expect("JUCE not restored"),
+        "project(JUCE VERSION 8.0.4)\n"
+    );
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+    fs::remove_dir_all(&clone_path).expect("Failed to clean up cloned project directory");
+    fs::remove_dir_all(&juce_repo_path).expect("Failed to clean up JUCE repository");
+}
+
+#[test]
+fn test_juce_fetchcontent_mode() {
+    let default_fetch = resolve_source(Some(JuceMode::FetchContent), None).expect("Failed to resolve JUCE source");
+    assert_eq!(
+        default_fetch,
+        JuceSource::FetchContent {
+            url: String::from("https://github.com/juce-framework/JUCE.git"),
+            rev: Some(String::from("8.0.4")),
+            hash: None,
+        }
+    );
+    let archive = resolve_source(None, Some("https://example.com/JUCE-8.0.4.zip#SHA256=abc123"))
+        .expect("Failed to resolve JUCE source");
+    assert!(matches!(&archive, JuceSource::FetchContent { hash: Some(hash), .. } if hash == "SHA256=abc123"));
+    assert!(resolve_source(Some(JuceMode::Submodule), Some("https://example.com/JUCE.zip#SHA256=abc")).is_err());
+
+    let mut manifest = ProjectManifest::new("test_project", Some(String::from("ConsoleApp")));
+    manifest.juce = default_fetch;
+    let mut context = Context {
+        project_name: String::from("test_project"),
+        project_path: PathBuf::from("/tmp/juce_fetchcontent_test_project"),
+        template_name: Some(String::from("ConsoleApp")),
+        build_type: String::from("Release"),
+        manifest,
+    };
+    let juce_repo_path = PathBuf::from("/tmp/juce_fetchcontent_test_upstream");
+    create_juce_repository(&juce_repo_path, &["8.0.4"]);
+    let _ = fs::remove_dir_all(&context.project_path); // Clean up before running the test
+    fs::create_dir_all(&context.project_path).expect("Failed to create test project directory");
+    context.manifest.write(&context.project_path).expect("Failed to write jumake.toml");
+    create_cmakelists(&context).expect("Failed to create CMakeLists.txt");
+    create_source_files(&context).expect("Failed to create source files");
+    initialize_git_repo(&context).expect("Failed to initialize Git repository");
+
+    let root_path = context.project_path.join("CMakeLists.txt");
+    let root = fs::read_to_string(&root_path).expect("Failed to read CMakeLists.txt");
+    assert!(root.contains(indoc! {"
+        FetchContent_Declare(JUCE
+            GIT_REPOSITORY https://github.com/juce-framework/JUCE.git
+            GIT_TAG 8.0.4
+            GIT_SHALLOW ON)
+        FetchContent_MakeAvailable(JUCE)
+        add_subdirectory(src)
+    "}));
+    assert!(!root.contains("add_subdirectory(modules/JUCE)"));
+
+    // FetchContent → submodule → FetchContent from an archive
+    let submodule = format!("git:file://{}@8.0.4", juce_repo_path.display());
+    let source = resolve_source(Some(JuceMode::Submodule), Some(&submodule)).expect("Failed to resolve JUCE source");
+    switch_juce_source(&mut context, source).expect("Failed to switch to submodule mode");
+    let root = fs::read_to_string(&root_path).expect("Failed to read CMakeLists.txt");
+    assert!(root.contains("project(test_project VERSION 0.0.1)\nadd_subdirectory(modules/JUCE)\nadd_subdirectory(src)\n"));
+    assert!(!root.contains("FetchContent"));
+    assert!(context.project_path.join("modules/JUCE/CMakeLists.txt").is_file());
+    assert!(context.project_path.join(".gitmodules").is_file());
+
+    switch_juce_source(&mut context, archive.clone()).expect("Failed to switch to FetchContent mode");
+    let root = fs::read_to_string(&root_path).expect("Failed to read CMakeLists.txt");
+    assert!(root.contains("FetchContent_Declare(JUCE\n    URL https://example.com/JUCE-8.0.4.zip\n    URL_HASH SHA256=abc123)\n"));
+    assert!(!root.contains("add_subdirectory(modules/JUCE)"));
+    assert!(!context.project_path.join("modules/JUCE").exists());
+    assert!(!context.project_path.join(".gitmodules").exists());
+    assert_eq!(ProjectManifest::read(&context.project_path).expect("Failed to read jumake.toml").juce, archive);
+
+    fs::remove_dir_all(&context.project_path).expect("Failed to clean up test project directory");
+    fs::remove_dir_all(&juce_repo_path).expect("Failed to clean up JUCE repository");
+}
+
+#[test]
+fn test_juce_installs() {
+    let juce7 = PathBuf::from("/tmp/juce_installs_test/JUCE7");
+    let juce8 = PathBuf::from("/tmp/juce_installs_test/JUCE8");
+    let _ = fs::remove_dir_all("/tmp/juce_installs_test"); // Clean up before running the test
+    for (path, cmake) in [
+        (&juce7, "cmake_minimum_required(VERSION 3.22)\nproject(JUCE VERSION 7.0.12 LANGUAGES C CXX)\n"),
+        (&juce8, "project(JUCE\n    VERSION 8.0.4\n    LANGUAGES C CXX)\n"),
+    ] {
+        fs::create_dir_all(path).expect("Failed to create fake JUCE folder");
+        fs::write(path.join("CMakeLists.txt"), cmake).expect("Failed to write JUCE CMakeLists.txt");
+    }
+    assert_eq!(read_juce_version(&juce7).as_deref(), Some("7.0.12"));
+    assert_eq!(read_juce_version(&juce8).as_deref(), Some("8.0.4"));
+    assert_eq!(read_juce_version(&PathBuf::from("/tmp/juce_installs_test")), None);
+
+    let mut config = JuMakeConfig::default();
+    config.add_install("juce8", juce8.clone());
+    config.add_install("juce7", juce7.clone());
+    assert_eq!(config.default_juce.as_deref(), Some("juce8"), "The first install becomes the default");
+    assert_eq!(config.find_install("7.0.12").map(|(name, _)| name.as_str()), Some("juce7"));
+    assert_eq!(config.find_install("juce8").map(|(_, install)| install.path.clone()), Some(juce8.clone()));
+    assert!(config.find_install("6.1.6").is_none());
+
+    let serialized = toml::to_string(&config).expect("Failed to serialize config");
+    let reloaded: JuMakeConfig = toml::from_str(&serialized).expect("Failed to parse config");
+    assert_eq!(reloaded.juce_installs, config.juce_installs);
+    assert_eq!(reloaded.default_juce, config.default_juce);
+
+    assert_eq!(
+        "symlink:7.0.12".parse::<JuceSource>().expect("Failed to parse JUCE source"),
+        JuceSource::Symlink { version: Some(String::from("7.0.12")) }
+    );
+
+    fs::remove_dir_all("/tmp/juce_installs_test").expect("Failed to clean up JUCE folders");
+}
+
+#[test]
+fn test_doctor_checks() {
+    assert!(version_at_least("3.28.3", "3.24"));
+    assert!(version_at_least("3.24", "3.24"));
+    assert!(version_at_least("4.0.0-rc1", "3.24"));
+    assert!(!version_at_least("3.22.1", "3.24"));
+
+    let project_path = PathBuf::from("/tmp/doctor_test_project");
+    let _ = fs::remove_dir_all(&project_path); // Clean up before running the test
+    fs::create_dir_all(&project_path).expect("Failed to create test project directory");
+    let mut manifest = ProjectManifest::new("doctor_project", None);
+    assert_eq!(check_build_dir(&project_path, Some(&manifest)).status, Status::Ok, "Not configured yet");
+
+    let build_dir = project_path.join("jumake_build");
+    fs::create_dir_all(&build_dir).expect("Failed to create build directory");
+    assert_eq!(check_build_dir(&project_path, Some(&manifest)).status, Status::Warning, "No CMakeCache.txt");
+
+    let write_cache = |home: &str| {
+        let cache = format!(
+            "# This is the CMakeCache file.\n//Build type\nCMAKE_BUILD_TYPE:STRING=Debug\n\
+             CMAKE_GENERATOR:INTERNAL=Unix Makefiles\nCMAKE_HOME_DIRECTORY:INTERNAL={}\n",
+            home
+        );
+        fs::write(build_dir.join("CMakeCache.txt"), cache).expect("Failed to write CMakeCache.txt");
+    };
+    write_cache("/tmp/doctor_test_project");
+    let check = check_build_dir(&project_path, Some(&manifest));
+    assert_eq!(check.status, Status::Ok);
+    assert_eq!(check.detail, "Configured with Unix Makefiles (Debug)");
+
+    manifest.build.generator = Some(String::from("Ninja"));
+    assert_eq!(check_build_dir(&project_path, Some(&manifest)).status, Status::Warning, "Generator changed");
+
+    // A project copied from elsewhere keeps the old cache
+    write_cache("/tmp/somewhere_else");
+    let check = check_build_dir(&project_path, None);
+    assert_eq!(check.status, Status::Error);
+    assert!(check.fix.is_some());
+
+    let report = Report {
+        checks: vec![
+            Check { name: String::from("cmake"), status: Status::Ok, detail: String::from("CMake 3.28"), fix: None },
+            Check {
+                name: String::from("build directory"),
+                status: Status::Error,
+                detail: String::from("configured for \"C:\\old\"\n"),
+                fix: Some(String::from("Delete jumake_build/")),
+            },
+        ],
+    };
+    assert_eq!(report.count(Status::Error), 1);
+    assert_eq!(
+        report.to_json(),
+        concat!(
+            r#"{"ok":false,"errors":1,"warnings":0,"checks":["#,
+            r#"{"name":"cmake","status":"ok","detail":"CMake 3.28","fix":null},"#,
+            r#"{"name":"build directory","status":"error","detail":"configured for \"C:\\old\"\n","#,
+            r#""fix":"Delete jumake_build/"}]}"#
+        )
+    );
+
+    fs::remove_dir_all(&project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_parse_diagnostics() {
+    let output = indoc! {r#"
+        [1/3] Building CXX object CMakeFiles/MyApp.dir/src/Main.cpp.o
+        FAILED: CMakeFiles/MyApp.dir/src/Main.cpp.o
+        In file included from /work/MyApp/src/Main.cpp:2:
+        /work/MyApp/src/Helper.h:7:10: warning: unused variable 'x' [-Wunused-variable]
+            7 |     int x = 0;
+              |         ^
+        /work/MyApp/src/Main.cpp:12:5: error: use of undeclared identifier 'foo'
+        /work/MyApp/src/Helper.h:3:6: note: did you mean 'food'?
+        /work/MyApp/src/Main.cpp: In function 'int main()':
+        ../src/Other.cpp:4: error: expected ';' before '}' token
+        [2/3] Building CXX object CMakeFiles/MyApp.dir/src/Other.cpp.o
+        /work/MyApp/src/Helper.h:7:10: warning: unused variable 'x' [-Wunused-variable]
+        /work/MyApp/src/Helper.h:1:1: note: repeated with the duplicate warning
+        2 errors generated.
+        /usr/bin/ld: cannot find -lfoo
+    "#};
+    // Colored output
+    let output = output.replacen(
+        "/work/MyApp/src/Main.cpp:12:5: error:",
+        "\x1b[1m/work/MyApp/src/Main.cpp:12:5: \x1b[31merror:\x1b[0m",
+        1,
+    );
+    let diagnostics = Diagnostics::parse(&output, &PathBuf::from("/work/MyApp/jumake_build"));
+
+    assert_eq!(diagnostics.diagnostics.len(), 3, "Duplicates and unrelated lines are skipped");
+    assert_eq!(diagnostics.count(Severity::Error), 2);
+    assert_eq!(diagnostics.count(Severity::Warning), 1);
+
+    let error = &diagnostics.diagnostics[1];
+    assert_eq!(error.file, "/work/MyApp/src/Main.cpp");
+    assert_eq!((error.line, error.column), (12, Some(5)));
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(error.message, "use of undeclared identifier 'foo'");
+    assert_eq!(error.notes.len(), 1);
+    assert_eq!(error.notes[0].message, "did you mean 'food'?");
+    assert!(diagnostics.diagnostics[0].notes.is_empty(), "Notes of a duplicate are dropped too");
+
+    let relative = &diagnostics.diagnostics[2];
+    assert_eq!(relative.file, "/work/MyApp/src/Other.cpp");
+    assert_eq!(relative.column, None);
+
+    assert_eq!(
+        diagnostics.to_gcc(),
+        vec![
+            "/work/MyApp/src/Helper.h:7:10: warning: unused variable 'x' [-Wunused-variable]",
+            "/work/MyApp/src/Main.cpp:12:5: error: use of undeclared identifier 'foo'",
+            "/work/MyApp/src/Helper.h:3:6: note: did you mean 'food'?",
+            "/work/MyApp/src/Other.cpp:4: error: expected ';' before '}' token",
+        ]
+    );
+    let json = diagnostics.to_json(&PathBuf::from("/work/MyApp/jumake_build/build.log"));
+    assert!(json.starts_with(r#"{"errors":2,"warnings":1,"log":"/work/MyApp/jumake_build/build.log","diagnostics":["#));
+    assert!(json.contains(concat!(
+        r#"{"file":"/work/MyApp/src/Main.cpp","line":12,"column":5,"severity":"error","#,
+        r#""message":"use of undeclared identifier 'foo'","notes":[{"file":"/work/MyApp/src/Helper.h","#,
+        r#""line":3,"column":6,"severity":"note","message":"did you mean 'food'?","notes":[]}]}"#
+    )));
+}
+
+#[test]
+fn test_cmake_presets() {
+    let project_path = PathBuf::from("/tmp/presets_test_project");
+    let _ = fs::remove_dir_all(&project_path); // Clean up before running the test
+    fs::create_dir_all(&project_path).expect("Failed to create test project directory");
+
+    let mut manifest = ProjectManifest::new("presets_project", None);
+    manifest.build.generator = Some(String::from("Ninja"));
+    manifest.build.cmake_args =
+        ["-DJUCE_BUILD_EXTRAS=ON", "-D", "USE_ASAN:BOOL=OFF", "-Wno-dev"].map(String::from).to_vec();
+    let (presets, other_args) = generate_presets(&manifest);
+    assert_eq!(other_args, vec!["-Wno-dev"]);
+    let document = serde_json::from_str::<Value>(&presets).expect("Generated presets are valid JSON");
+    let configure_presets = document.get("configurePresets").and_then(Value::as_array).unwrap();
+    assert_eq!(configure_presets.len(), 5, "A hidden base preset and one per build type");
+    let base = &configure_presets[0];
+    assert_eq!(base.get("generator").and_then(Value::as_str), Some("Ninja"));
+    let variables = base.get("cacheVariables").unwrap();
+    assert_eq!(variables.get("CMAKE_EXPORT_COMPILE_COMMANDS").and_then(Value::as_str), Some("ON"));
+    assert_eq!(variables.get("JUCE_BUILD_EXTRAS").and_then(Value::as_str), Some("ON"));
+    assert_eq!(variables.get("USE_ASAN").and_then(Value::as_str), Some("OFF"));
+    assert!(base.get("toolchainFile").is_none());
+    let mut toolchain = manifest.clone();
+    toolchain.build.toolchain = Some(String::from("cmake/clang.cmake"));
+    let (presets, _) = generate_presets(&toolchain);
+    let document = serde_json::from_str::<Value>(&presets).expect("Generated presets are valid JSON");
+    let base = &document.get("configurePresets").and_then(Value::as_array).unwrap()[0];
+    assert_eq!(base.get("toolchainFile").and_then(Value::as_str), Some("${sourceDir}/cmake/clang.cmake"));
+
+    let context = Context {
+        project_name: String::from("presets_project"),
+        project_path: project_path.clone(),
+        template_name: None,
+        build_type: String::from("Release"),
+        manifest,
+    };
+    write_presets(&context).expect("Failed to write CMakePresets.json");
+
+    let preset = find_preset(&project_path, "relwithdebinfo").expect("Generated preset not found");
+    assert_eq!(preset.configure_preset, "relwithdebinfo");
+    assert_eq!(preset.build_preset.as_deref(), Some("relwithdebinfo"));
+    assert_eq!(preset.binary_dir, project_path.join("jumake_build/RelWithDebInfo"), "A directory per build type");
+    assert_eq!(preset.generator.as_deref(), Some("Ninja"), "Inherited from the base preset");
+    assert_eq!(preset.build_type.as_deref(), Some("RelWithDebInfo"));
+
+    // Multi-config generators build every configuration in one directory
+    let mut multi_config = context.manifest.clone();
+    multi_config.build.generator = Some(String::from("Ninja Multi-Config"));
+    let (presets, _) = generate_presets(&multi_config);
+    assert!(presets.contains(r#""binaryDir": "${sourceDir}/jumake_build""#), "{}", presets);
+    assert!(!presets.contains("jumake_build/Debug"), "{}", presets);
+
+    // User presets can build on the project's presets
+    let user_presets = indoc! {r#"
+        {
+          "version": 3,
+          "configurePresets": [
+            {
+              "name": "asan",
+              "inherits": ["debug"],
+              "binaryDir": "${sourceDir}/build/${presetName}",
+              "cacheVariables": { "USE_ASAN": { "type": "BOOL", "value": "ON" } }
+            }
+          ]
+        }
+    "#};
+    fs::write(project_path.join(USER_PRESETS_FILE), user_presets).expect("Failed to write user presets");
+    let preset = find_preset(&project_path, "asan").expect("User preset not found");
+    assert_eq!(preset.build_preset, None, "Built in its binary directory without a build preset");
+    assert_eq!(preset.binary_dir, project_path.join("build/asan"));
+    assert_eq!(preset.build_type.as_deref(), Some("Debug"));
+
+    let error = find_preset(&project_path, "jumake-base").unwrap_err().to_string();
+    assert!(error.contains("hidden"), "{}", error);
+    let error = find_preset(&project_path, "missing").unwrap_err().to_string();
+    assert!(error.contains("debug, release, relwithdebinfo, minsizerel, asan"), "{}", error);
+
+    fs::write(project_path.join(USER_PRESETS_FILE), "{\n  \"version\": 3,\n  \"configurePresets\": [\n").unwrap();
+    let error = format!("{:#}", find_preset(&project_path, "debug").unwrap_err());
+    assert!(error.contains("line 4"), "{}", error);
+
+    fs::remove_dir_all(&project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_build_directories() {
+    let project_path = PathBuf::from("/tmp/build_dirs_test_project");
+    let _ = fs::remove_dir_all(&project_path); // Clean up before running the test
+
+    assert!(is_multi_config("Ninja Multi-Config"));
+    assert!(is_multi_config("Visual Studio 17 2022"));
+    assert!(!is_multi_config("Ninja"));
+    assert_eq!(build_directory(&project_path, "Ninja", "Debug"), project_path.join("jumake_build/Debug"));
+    assert_eq!(build_directory(&project_path, "Xcode", "Debug"), project_path.join("jumake_build"));
+
+    assert!(configured_build_dirs(&project_path).is_empty());
+    for build_type in ["Release", "Debug"] {
+        let build_dir = project_path.join("jumake_build").join(build_type);
+        fs::create_dir_all(&build_dir).expect("Failed to create build directory");
+        let cache = format!(
+            "CMAKE_BUILD_TYPE:STRING={}\nCMAKE_GENERATOR:INTERNAL=Ninja\nCMAKE_HOME_DIRECTORY:INTERNAL={}\n",
+            build_type,
+            project_path.display()
+        );
+        fs::write(build_dir.join("CMakeCache.txt"), cache).expect("Failed to write CMakeCache.txt");
+    }
+    fs::create_dir_all(project_path.join("jumake_build/Unconfigured")).expect("Failed to create directory");
+    assert_eq!(
+        configured_build_dirs(&project_path),
+        vec![project_path.join("jumake_build/Debug"), project_path.join("jumake_build/Release")]
+    );
+
+    let check = check_build_dir(&project_path, None);
+    assert_eq!(check.status, Status::Ok);
+    assert_eq!(check.detail, "Configured with Ninja (Debug), Ninja (Release)");
+    let mut manifest = ProjectManifest::new("build_dirs_project", None);
+    manifest.build.generator = Some(String::from("Unix Makefiles"));
+    let check = check_build_dir(&project_path, Some(&manifest));
+    assert_eq!(check.status, Status::Warning, "Generator changed");
+    assert!(check.detail.starts_with("jumake_build/Debug/ was configured with 'Ninja'"), "{}", check.detail);
+
+    fs::remove_dir_all(&project_path).expect("Failed to clean up test project directory");
+}
+
+#[test]
+fn test_build_overrides() {
+    let project_path = PathBuf::from("/tmp/build_overrides_test_project");
+    let _ = fs::remove_dir_all(&project_path); // Clean up before running the test
+    fs::create_dir_all(project_path.join("cmake")).expect("Failed to create test project directory");
+    fs::write(project_path.join("cmake/clang.cmake"), "").expect("Failed to write toolchain file");
+
+    let mut context = Context {
+        project_name: String::from("overrides_project"),
+        project_path: project_path.clone(),
+        template_name: None,
+        build_type: String::from("Debug"),
+        manifest: ProjectManifest::new("overrides_project", None),
+    };
+    let overrides = BuildOverrides {
+        define: vec![String::from("JUCE_BUILD_EXTRAS=ON"), String::from("USE_ASAN:BOOL=OFF")],
+        toolchain: Some(project_path.join("cmake/../cmake/clang.cmake")),
+        generator: Some(String::from("Ninja")),
+        target: Some(String::from("overrides_project_VST3")),
+        jobs: Some(3),
+        build_args: vec![String::from("--verbose")],
+        reset: false,
+    };
+    let settings = &mut context.manifest.build;
+    settings.apply(overrides, &project_path).expect("Failed to apply build options");
+    assert_eq!(settings.toolchain.as_deref(), Some("cmake/clang.cmake"), "Kept relative to the project");
+    assert_eq!(
+        settings.build_step_args(),
+        vec!["--target overrides_project_VST3", "--jobs 3", "-- --verbose"]
+    );
+
+    // Options not given keep their saved values
+    let saved = settings.clone();
+    settings.apply(BuildOverrides::default(), &project_path).expect("Failed to apply build options");
+    assert_eq!(*settings, saved);
+    let overrides = BuildOverrides { define: vec![String::from("USE_ASAN=ON")], jobs: Some(8), ..Default::default() };
+    settings.apply(overrides, &project_path).expect("Failed to apply build options");
+    assert_eq!(settings.cmake_args, vec!["-DJUCE_BUILD_EXTRAS=ON", "-DUSE_ASAN=ON"]);
+    assert_eq!(settings.jobs, Some(8));
+    assert_eq!(settings.target.as_deref(), Some("overrides_project_VST3"));
+
+    let invalid = BuildOverrides { jobs: Some(0), ..Default::default() };
+    assert!(settings.apply(invalid, &project_path).is_err(), "--jobs 0 is rejected");
+    let invalid = BuildOverrides { toolchain: Some(project_path.join("missing.cmake")), ..Default::default() };
+    assert!(settings.apply(invalid, &project_path).is_err(), "A missing toolchain file is rejected");
+    assert_eq!(settings.jobs, Some(8), "A rejected option changes nothing");
+
+    // The saved options end up on the command lines
+    let options = BuildOptions::default();
+    let build_dir = build_directory(&project_path, "Ninja", "Debug");
+    let args = |command: &std::process::Command| -> Vec<String> {
+        command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+    };
+    let configure = args(&configure_command(&context, &options, "Ninja", &build_dir));
+    let toolchain = format!("-DCMAKE_TOOLCHAIN_FILE={}", project_path.join("cmake/clang.cmake").display());
+    for expected in ["-GNinja", "-DCMAKE_BUILD_TYPE=Debug", &toolchain, "-DJUCE_BUILD_EXTRAS=ON", "-DUSE_ASAN=ON"] {
+        assert!(configure.iter().any(|arg| arg == expected), "{} missing in {:?}", expected, configure);
+    }
+    let build = args(&build_command(&context, &options, &build_dir));
+    assert_eq!(
+        build,
+        ["--build", ".", "--config", "Debug", "--parallel", "8", "--target", "overrides_project_VST3", "--verbose"]
+    );
+
+    // Resetting drops everything but the build type
+    let settings = &mut context.manifest.build;
+    settings.build_type = String::from("Debug");
+    let overrides = BuildOverrides { target: Some(String::from("all")), reset: true, ..Default::default() };
+    settings.apply(overrides, &project_path).expect("Failed to reset build options");
+    assert_eq!(settings.build_step_args(), vec!["--target all"]);
+    assert!(settings.cmake_args.is_empty() && settings.toolchain.is_none() && settings.generator.is_none());
+    assert_eq!(settings.build_type, "Debug");
+    let build = args(&build_command(&context, &options, &build_dir));
+    assert!(!build.contains(&String::from("--verbose")));
+
+    fs::remove_dir_all(&project_path).expect("Failed to clean up test project directory");
+}
+
+/// A `jumake` command with its configuration in `home` and without a terminal, so it never
+/// prompts and ignores the JuMake variables of the environment running the tests.
+fn jumake_command(home: &Path) -> std::process::Command {
+    let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_jumake"));
+    command
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env_remove("JUMAKE_JUCE_PATH")
+        .env_remove("JUMAKE_TEMPLATE")
+        .stdin(std::process::Stdio::null());
+    command
+}
+
+/// Writes a `cmake` standing in for the real one: it logs its arguments to `cmake.log` next to
+/// it and writes a CMakeCache.txt for the configure arguments it is given.
+fn create_fake_cmake(bin_dir: &Path) {
+    fs::create_dir_all(bin_dir).expect("Failed to create bin directory");
+    let script = format!(
+        indoc! {r#"
+            #!/bin/sh
+            echo "$*" >> {}/cmake.log
+            [ "$1" = "--build" ] && exit 0
+            build_type=""; generator=""; source=""; previous=""
+            for arg in "$@"; do
+                case "$arg" in
+                    -G*) generator="${{arg#-G}}";;
+                    -DCMAKE_BUILD_TYPE=*) build_type="${{arg#-DCMAKE_BUILD_TYPE=}}";;
+                esac
+                [ "$previous" = "-S" ] && source="$arg"
+                previous="$arg"
+            done
+            printf 'CMAKE_BUILD_TYPE:STRING=%s\nCMAKE_GENERATOR:INTERNAL=%s\nCMAKE_HOME_DIRECTORY:INTERNAL=%s\n' \
+                "$build_type" "$generator" "$source" > CMakeCache.txt
+            touch compile_commands.json
+        "#},
+        bin_dir.display()
+    );
+    let cmake = bin_dir.join("cmake");
+    fs::write(&cmake, script).expect("Failed to write fake cmake");
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(&cmake, fs::Permissions::from_mode(0o755)).expect("Failed to make fake cmake executable");
+}
+
+#[test]
+fn test_configure_inputs() {
+    let parent = PathBuf::from("/tmp/configure_inputs_test");
+    let _ = fs::remove_dir_all(&parent); // Clean up before running the test
+    let bin_dir = parent.join("bin");
+    create_fake_cmake(&bin_dir);
+    let mut manifest = ProjectManifest::new("inputs_project", Some(String::from("ConsoleApp")));
+    manifest.juce = resolve_source(Some(JuceMode::FetchContent), None).expect("Failed to resolve JUCE source");
+    manifest.build.generator = Some(String::from("Unix Makefiles"));
+    let context = Context {
+        project_name: String::from("inputs_project"),
+        project_path: parent.join("inputs_project"),
+        template_name: manifest.project.template.clone(),
+        build_type: String::from("Debug"),
+        manifest,
+    };
+    create_project(&context, None).expect("Failed to create project");
+
+    // Runs jumake in the project with the fake cmake and returns its output and the cmake calls
+    let log = bin_dir.join("cmake.log");
+    let jumake = |args: &[&str], cxx: &str| -> (String, Vec<String>) {
+        let _ = fs::remove_file(&log);
+        let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default());
+        let output = jumake_command(&parent)
+            .args(args)
+            .current_dir(&context.project_path)
+            .env("PATH", path)
+            .env_remove("CC")
+            .env("CXX", cxx)
+            .output()
+            .expect("Failed to run jumake");
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        assert!(output.status.success(), "jumake {:?} failed: {}{}", args, stdout, String::from_utf8_lossy(&output.stderr));
+        let calls = fs::read_to_string(&log).unwrap_or_default().lines().map(String::from).collect();
+        (stdout, calls)
+    };
+    let configures = |calls: &[String]| calls.iter().filter(|call| call.starts_with("-S")).count();
+
+    let (stdout, calls) = jumake(&["configure", "-t", "Debug"], "");
+    assert!(stdout.contains("Running CMake configuration..."), "{}", stdout);
+    assert_eq!(configures(&calls), 1);
+    assert!(!calls[0].contains("--fresh"));
+    let build_dir = build_directory(&context.project_path, "Unix Makefiles", "Debug");
+    let recorded = fs::read_to_string(build_dir.join("jumake-configure-inputs.txt")).expect("No configure inputs recorded");
+    assert!(recorded.contains(&format!("command = cmake {}", calls[0])), "{}", recorded);
+    assert!(recorded.contains("JUCE = fetchcontent "), "{}", recorded);
+    assert!(!recorded.contains("presets = "), "Presets only matter to preset builds: {}", recorded);
+
+    // Nothing changed: the build skips the configure step, unless asked to configure again
+    let (stdout, calls) = jumake(&["build", "-t", "Debug"], "");
+    assert!(stdout.contains("CMake already configured, skipping configure step..."), "{}", stdout);
+    assert_eq!(configures(&calls), 0);
+    let (stdout, calls) = jumake(&["build", "-t", "Debug", "--reconfigure"], "");
+    assert!(stdout.contains("Configuring again..."), "{}", stdout);
+    assert_eq!(configures(&calls), 1);
+    assert!(!calls[0].contains("--fresh"));
+
+    // Editing the presets doesn't touch builds without a preset
+    fs::write(context.project_path.join(USER_PRESETS_FILE), "{\"version\": 6}\n").expect("Failed to write user presets");
+    let (_, calls) = jumake(&["build", "-t", "Debug"], "");
+    assert_eq!(configures(&calls), 0, "{:?}", calls);
+
+    // Changed compilers need a fresh cache; --fresh asks for one
+    let (stdout, calls) = jumake(&["build", "-t", "Debug"], "clang++");
+    assert!(stdout.contains("The configure input 'CXX' changed — configuring from scratch..."), "{}", stdout);
+    assert!(calls[0].ends_with("--fresh"), "{:?}", calls);
+    let (_, calls) = jumake(&["build", "-t", "Debug"], "clang++");
+    assert_eq!(configures(&calls), 0, "The new inputs were recorded");
+    let (stdout, calls) = jumake(&["configure", "-t", "Debug", "--fresh"], "clang++");
+    assert!(stdout.contains("Configuring from scratch..."), "{}", stdout);
+    assert!(calls[0].ends_with("--fresh"), "{:?}", calls);
+
+    // A changed command line configures again, keeping the cache
+    let (stdout, calls) = jumake(&["build", "-t", "Debug", "-D", "JUCE_BUILD_EXTRAS=ON"], "clang++");
+    assert!(stdout.contains("configuring again..."), "{}", stdout);
+    assert!(calls[0].contains("-DJUCE_BUILD_EXTRAS=ON") && !calls[0].contains("--fresh"), "{:?}", calls);
+
+    fs::remove_dir_all(&parent).expect("Failed to clean up test directory");
+}
+
+#[test]
+fn test_clean_project() {
+    let project_path = PathBuf::from("/tmp/clean_test_project");
+    let outside_path = PathBuf::from("/tmp/clean_test_outside");
+    let _ = fs::remove_dir_all(&project_path); // Clean up before running the test
+    let _ = fs::remove_dir_all(&outside_path);
+    let configure = |build_dir: &Path| {
+        fs::create_dir_all(build_dir.join("CMakeFiles")).expect("Failed to create build directory");
+        fs::write(build_dir.join("CMakeCache.txt"), "CMAKE_GENERATOR:INTERNAL=Ninja\n")
+            .expect("Failed to write CMakeCache.txt");
+        fs::write(build_dir.join("app.o"), "").expect("Failed to write build output");
+    };
+    configure(&project_path.join("jumake_build/Debug"));
+    configure(&project_path.join("jumake_build/Release"));
+    fs::write(project_path.join("compile_commands.json"), "[]").expect("Failed to write compile_commands.json");
+    fs::write(project_path.join(".jumake"), "Debug").expect("Failed to write .jumake");
+
+    let options = |mode, dry_run| CleanOptions { mode, dry_run };
+    clean_project(&project_path, options(CleanMode::Cache, true)).expect("Failed to list the cache files");
+    clean_project(&project_path, options(CleanMode::All, true)).expect("Failed to list the build directories");
+    assert!(project_path.join("jumake_build/Debug/CMakeCache.txt").exists(), "A dry run deletes nothing");
+
+    clean_project(&project_path, options(CleanMode::Cache, false)).expect("Failed to delete the CMake caches");
+    for build_type in ["Debug", "Release"] {
+        let build_dir = project_path.join("jumake_build").join(build_type);
+        assert!(!build_dir.join("CMakeCache.txt").exists() && !build_dir.join("CMakeFiles").exists());
+        assert!(build_dir.join("app.o").exists(), "Build outputs are kept");
+    }
+    assert!(configured_build_dirs(&project_path).is_empty());
+
+    clean_project(&project_path, options(CleanMode::All, false)).expect("Failed to delete the build directories");
+    for name in ["jumake_build", "compile_commands.json", ".jumake"] {
+        assert!(!project_path.join(name).exists(), "{} must be deleted", name);
+    }
+    clean_project(&project_path, options(CleanMode::Outputs, false)).expect("Nothing to clean is not an error");
+
+    // A build directory linked from elsewhere is not cleaned through the link
+    configure(&outside_path.join("Debug"));
+    std::os::unix::fs::symlink(&outside_path, project_path.join("jumake_build")).expect("Failed to link build directory");
+    let error = clean_project(&project_path, options(CleanMode::Cache, false)).expect_err("Must refuse to delete");
+    assert!(error.to_string().contains("outside the project root"), "{}", error);
+    assert!(outside_path.join("Debug/CMakeCache.txt").exists());
+    clean_project(&project_path, options(CleanMode::All, false)).expect("Failed to delete the link");
+    assert!(project_path.join("jumake_build").symlink_metadata().is_err(), "The link is deleted");
+    assert!(outside_path.join("Debug/CMakeCache.txt").exists(), "Its target is kept");
+
+    fs::remove_dir_all(&project_path).expect("Failed to clean up test project directory");
+    fs::remove_dir_all(&outside_path).expect("Failed to clean up test directory");
+}
+
+#[test]
+fn test_config_settings() {
+    let juce_path = PathBuf::from("/tmp/config_settings_test/JUCE");
+    let _ = fs::remove_dir_all("/tmp/config_settings_test"); // Clean up before running the test
+    fs::create_dir_all(&juce_path).expect("Failed to create fake JUCE folder");
+    fs::write(juce_path.join("CMakeLists.txt"), "project(JUCE VERSION 8.0.4)\n").expect("Failed to write JUCE CMakeLists.txt");
+
+    let mut config = JuMakeConfig::default();
+    for (key, _) in CONFIG_KEYS {
+        assert_eq!(config.get(key).expect("Failed to get setting"), None, "{} is not set by default", key);
+    }
+    assert!(config.get("juce").is_err(), "Unknown keys are rejected");
+
+    config.set("author_name", "Jane Doe").expect("Failed to set author_name");
+    config.set("manufacturer_code", "Acme").expect("Failed to set manufacturer_code");
+    config.set("default_build_type", "Debug").expect("Failed to set default_build_type");
+    assert!(config.set("manufacturer_code", "acme").is_err(), "Needs an upper-case character");
+    assert!(config.set("manufacturer_code", "Acme1").is_err(), "Needs four characters");
+    config.set("company_name", "Acme Audio & Co.").expect("Failed to set company_name");
+    for name in ["Acme \"Audio\"", "Acme\\Audio", "Acme;Audio", "${Acme}"] {
+        assert!(config.set("company_name", name).is_err(), "{} is rejected", name);
+    }
+    assert!(config.set("default_build_type", "Fast").is_err());
+    assert!(config.set("default_juce", "juce-8.0.4").is_err(), "The install is not registered yet");
+    assert_eq!(config.get("author_name").expect("Failed to get setting").as_deref(), Some("Jane Doe"));
+    assert_eq!(config.manufacturer_code.as_deref(), Some("Acme"));
+    assert_eq!(config.company_name.as_deref(), Some("Acme Audio & Co."));
+
+    config.set("juce_path", "/tmp/config_settings_test/JUCE").expect("Failed to set juce_path");
+    assert_eq!(config.default_juce.as_deref(), Some("juce-8.0.4"));
+    assert_eq!(
+        config.get("juce_path").expect("Failed to get setting"),
+        Some(juce_path.canonicalize().expect("Failed to resolve JUCE folder").display().to_string())
+    );
+    assert!(config.set("juce_path", "/tmp/config_settings_test").is_err(), "Not a JUCE folder");
+
+    // Another folder of the same version doesn't replace the registered install
+    let other_path = PathBuf::from("/tmp/config_settings_test/JUCE-copy");
+    fs::create_dir_all(&other_path).expect("Failed to create fake JUCE folder");
+    fs::copy(juce_path.join("CMakeLists.txt"), other_path.join("CMakeLists.txt")).expect("Failed to copy CMakeLists.txt");
+    let error = config.set("juce_path", "/tmp/config_settings_test/JUCE-copy").expect_err("Conflicting install");
+    assert!(error.to_string().starts_with("The JUCE install 'juce-8.0.4' already points at /tmp/config_settings_test/JUCE."), "{}", error);
+    assert_eq!(config.juce_installs.len(), 1);
+    assert_eq!(config.juce_installs["juce-8.0.4"].path, juce_path.canonicalize().expect("Failed to resolve JUCE folder"));
+    config.set("juce_path", "/tmp/config_settings_test/JUCE/").expect("Setting the same folder again is fine");
+    assert!(config.unset("juce_path").is_err(), "Installs are removed with `jumake juce remove`");
+
+    config.unset("author_name").expect("Failed to unset author_name");
+    assert_eq!(config.author_name, None);
+
+    let serialized = toml::to_string(&config).expect("Failed to serialize config");
+    let reloaded: JuMakeConfig = toml::from_str(&serialized).expect("Failed to parse config");
+    assert_eq!(reloaded.manufacturer_code.as_deref(), Some("Acme"));
+    assert_eq!(reloaded.default_build_type.as_deref(), Some("Debug"));
+
+    fs::remove_dir_all("/tmp/config_settings_test").expect("Failed to clean up test folders");
+}
+
+#[test]
+fn test_create_project_rollback() {
+    let parent = PathBuf::from("/tmp/create_project_test");
+    let _ = fs::remove_dir_all(&parent); // Clean up before running the test
+    let mut manifest = ProjectManifest::new("broken_project", Some(String::from("NoSuchTemplate")));
+    // FetchContent projects need no local JUCE
+    manifest.juce = resolve_source(Some(JuceMode::FetchContent), None).expect("Failed to resolve JUCE source");
+    let mut context = Context {
+        project_name: String::from("broken_project"),
+        project_path: parent.join("broken_project"),
+        template_name: manifest.project.template.clone(),
+        build_type: String::from("Release"),
+        manifest,
+    };
+
+    let error = create_project(&context, None).expect_err("Unknown templates must fail");
+    assert!(matches!(error, JuMakeError::CreateStep { step: "source files", .. }), "{:?}", error);
+    assert!(!context.project_path.exists(), "A failed project is removed");
+    assert_eq!(fs::read_dir(&parent).expect("Failed to list parent directory").count(), 0, "No staging directory is left");
+
+    context.template_name = Some(String::from("ConsoleApp"));
+    context.manifest.project.template = Some(String::from("ConsoleApp"));
+    create_project(&context, None).expect("Failed to create project");
+    assert!(context.project_path.join("jumake.toml").is_file());
+    assert!(context.project_path.join("src/Main.cpp").is_file());
+    assert!(context.project_path.join(".git").is_dir());
+    assert_eq!(fs::read_dir(&parent).expect("Failed to list parent directory").count(), 1);
+
+    let error = create_project(&context, None).expect_err("Existing directories must not be overwritten");
+    assert!(matches!(error, JuMakeError::ProjectExists(_)));
+    assert!(context.project_path.join("src/Main.cpp").is_file());
+
+    fs::remove_dir_all(&parent).expect("Failed to clean up test directory");
+}
+
+#[test]
+fn test_non_interactive_new() {
+    let parent = PathBuf::from("/tmp/non_interactive_test");
+    let _ = fs::remove_dir_all(&parent); // Clean up before running the test
+    let juce_path = parent.join("SDKs/JUCE");
+    fs::create_dir_all(&juce_path).expect("Failed to create JUCE folder");
+    fs::write(juce_path.join("CMakeLists.txt"), "project(JUCE VERSION 8.0.4)\n").expect("Failed to write CMakeLists.txt");
+    let not_juce = parent.join("NotJUCE");
+    fs::create_dir_all(&not_juce).expect("Failed to create folder");
+    let new_project = |name: &str, envs: &[(&str, &Path)]| {
+        let mut command = jumake_command(&parent);
+        command.args(["new", name, "--path"]).arg(&parent).arg("--no-commit").envs(envs.iter().copied());
+        command.output().expect("Failed to run jumake")
+    };
+    let stderr = |output: &std::process::Output| String::from_utf8_lossy(&output.stderr).into_owned();
+
+    // Everything from the environment: no prompt needed
+    let output = new_project("EnvProject", &[("JUMAKE_JUCE_PATH", &juce_path), ("JUMAKE_TEMPLATE", Path::new("ConsoleApp"))]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let project_path = parent.join("EnvProject");
+    assert_eq!(fs::read_link(project_path.join("modules/JUCE")).expect("JUCE not linked"), juce_path);
+    let manifest = ProjectManifest::load(&project_path).expect("Failed to load manifest");
+    assert_eq!(manifest.project.template.as_deref(), Some("ConsoleApp"));
+
+    // Without an author the initial commit is skipped, not the project
+    let mut command = jumake_command(&parent);
+    command.args(["new", "NoAuthorProject", "--path"]).arg(&parent).env("JUMAKE_JUCE_PATH", &juce_path);
+    let output = command.args(["-t", "ConsoleApp"]).output().expect("Failed to run jumake");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No author for the initial commit"));
+    let repo = git2::Repository::open(parent.join("NoAuthorProject")).expect("Failed to open repository");
+    assert!(repo.head().is_err(), "Nothing is committed");
+    let index = repo.index().expect("Failed to open index");
+    assert!(index.get_path(Path::new("src/Main.cpp"), 0).is_some(), "The files are staged");
+
+    // A JUMAKE_JUCE_PATH without JUCE in it is a configuration error
+    let output = new_project("NotJuceProject", &[("JUMAKE_JUCE_PATH", &not_juce), ("JUMAKE_TEMPLATE", Path::new("ConsoleApp"))]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("JUMAKE_JUCE_PATH is set to /tmp/non_interactive_test/NotJUCE, which is not a JUCE folder"), "{}", stderr(&output));
+    assert!(!parent.join("NotJuceProject").exists());
+
+    // Without a terminal, missing answers are errors instead of prompts
+    let output = new_project("NoJuceProject", &[("JUMAKE_TEMPLATE", Path::new("ConsoleApp"))]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("No JUCE install found. Set JUMAKE_JUCE_PATH"), "{}", stderr(&output));
+    assert!(!parent.join("NoJuceProject").exists());
+    // ... unless JUCE is in one of the common locations, which is registered as an install
+    fs::create_dir_all(parent.join("JUCE")).expect("Failed to create JUCE folder");
+    fs::copy(juce_path.join("CMakeLists.txt"), parent.join("JUCE/CMakeLists.txt")).expect("Failed to copy CMakeLists.txt");
+    let output = new_project("HomeJuceProject", &[("JUMAKE_TEMPLATE", Path::new("ConsoleApp"))]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Found JUCE at /tmp/non_interactive_test/JUCE and registered it as install 'juce-8.0.4'."), "{}", stdout);
+    let output = new_project("NoTemplateProject", &[("JUMAKE_JUCE_PATH", &juce_path)]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("No template given: pass --template or set JUMAKE_TEMPLATE"), "{}", stderr(&output));
+    let output = new_project("BadTemplateProject", &[("JUMAKE_JUCE_PATH", &juce_path), ("JUMAKE_TEMPLATE", Path::new("NoSuchTemplate"))]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Unknown template: NoSuchTemplate"), "{}", stderr(&output));
+    assert!(!parent.join("NoTemplateProject").exists() && !parent.join("BadTemplateProject").exists());
+
+    // A broken user template says why it isn't available
+    let broken_template = parent.join("config/jumake/templates/Broken");
+    fs::create_dir_all(&broken_template).expect("Failed to create template directory");
+    fs::write(broken_template.join("template.toml"), "[[files]\n").expect("Failed to write template manifest");
+    let output = new_project("BrokenTemplateProject", &[("JUMAKE_JUCE_PATH", &juce_path), ("JUMAKE_TEMPLATE", Path::new("Broken"))]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Skipping template at /tmp/non_interactive_test/config/jumake/templates/Broken:"), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Unknown template: Broken"), "{}", stderr(&output));
+
+    fs::remove_dir_all(&parent).expect("Failed to clean up test directory");
+}
+
+#[test]
+fn test_cli_error_kinds() {
+    let error = CliError::from(JuMakeError::Config(String::from("No JUCE install found")));
+    assert_eq!(error.kind, ErrorKind::Config);
+    assert_eq!(error.kind.exit_code(), 3);
+
+    // The first known error in an anyhow chain decides the kind
+    let error = CliError::from(anyhow::Error::from(BuildError::CMakeBuildFailed).context("Building 'MyPlugin'"));
+    assert_eq!(error.kind, ErrorKind::Compile);
+    assert_eq!(error.messages(), vec!["Building 'MyPlugin'", "CMake build failed"]);
+    assert_eq!(CliError::from(anyhow::anyhow!("Something else")).kind, ErrorKind::Other);
+    assert_eq!(CliError::usage("Invalid build type: Fast").kind.exit_code(), 2);
+
+    // Invalid input from the command line is a usage error, also inside an anyhow chain
+    let context = Context {
+        project_name: String::from("kinds_project"),
+        project_path: PathBuf::from("/tmp/error_kinds_test_project"),
+        template_name: None,
+        build_type: String::from("Release"),
+        manifest: ProjectManifest::new("kinds_project", None),
+    };
+    let invalid_input = [
+        add_class(&context, "class", "1Filter", None).expect_err("Invalid class name"),
+        add_class(&context, "class", "Filter", Some("dsp::1x")).expect_err("Invalid namespace"),
+        add_class(&context, "widget", "Filter", None).expect_err("Invalid element type"),
+        ParameterSpec::new("gain", Some("0..1"), Some(2.0), None, false).expect_err("Default out of range"),
+        ProjectManifest::new("kinds_project", None).build.define("=ON").expect_err("Missing name"),
+        "copy".parse::<JuceSource>().expect_err("Invalid JUCE source"),
+        rename_class(&context, "Filter", "1Filter", EditOptions::default()).expect_err("Invalid rename target"),
+        rename_class(&context, "Missing", "Filter", EditOptions::default()).expect_err("Unknown class"),
+    ];
+    for error in invalid_input {
+        let error = CliError::from(error.context("Adding to 'kinds_project'"));
+        assert_eq!(error.kind, ErrorKind::Usage, "{:?}", error.messages());
+        assert_eq!(error.kind.exit_code(), 2);
+    }
+    assert!(!context.project_path.exists(), "Nothing is written for invalid input");
+
+    // Causes repeated by the message before them are not printed twice
+    let error = CliError::from(JuMakeError::Git(git2::Error::from_str("remote not found")));
+    assert_eq!(error.kind, ErrorKind::Git);
+    assert_eq!(error.messages(), vec!["Git error: remote not found"]);
+
+    let error = CliError::from(anyhow::Error::from(JuMakeError::Config(String::from("bad \"key\""))).context("Failed to load"));
+    assert_eq!(
+        error.to_json(),
+        r#"{"error":{"kind":"config","exit_code":3,"message":"Failed to load","causes":["Configuration error: bad \"key\""]}}"#
+    );
+}